@@ -0,0 +1,211 @@
+//! Generators of `arbitrary` values for fuzzing, available with the `arbitrary` feature.
+//!
+//! Values are generated within the ranges the telegram format can express, such that they
+//! survive being written to and parsed from a telegram.
+
+use core::fmt::Write;
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::types::{UFixedDouble, UFixedInteger, TST};
+use crate::Readout;
+
+/// The error for generated values that do not fit their buffer.
+fn overflow<E>(_: E) -> arbitrary::Error {
+    arbitrary::Error::IncorrectFormat
+}
+
+/// Write a timestamp as laid out in a telegram body, i.e. `(YYMMDDhhmmssX)`.
+fn write_tst(w: &mut impl Write, tst: &TST) -> core::fmt::Result {
+    write!(
+        w,
+        "({:02}{:02}{:02}{:02}{:02}{:02}{})",
+        tst.year,
+        tst.month,
+        tst.day,
+        tst.hour,
+        tst.minute,
+        tst.second,
+        if tst.dst { 'S' } else { 'W' }
+    )
+}
+
+/// Timestamps with fields in their calendar range, although not necessarily a valid date.
+impl<'a> Arbitrary<'a> for TST {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(TST {
+            year: u.int_in_range(0..=99)?,
+            month: u.int_in_range(1..=12)?,
+            day: u.int_in_range(1..=31)?,
+            hour: u.int_in_range(0..=23)?,
+            minute: u.int_in_range(0..=59)?,
+            second: u.int_in_range(0..=59)?,
+            dst: u.arbitrary()?,
+        })
+    }
+}
+
+/// Doubles of up to 10 digits, of which at least one is a decimal.
+impl<'a> Arbitrary<'a> for UFixedDouble {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let length: usize = u.int_in_range(2..=10)?;
+        let point: usize = u.int_in_range(1..=length - 1)?;
+
+        let mut body = heapless::String::<16>::new();
+        let mut push = |c| body.push(c).map_err(overflow);
+        push('(')?;
+        for i in 0..length {
+            if i == length - point {
+                push('.')?;
+            }
+            push(char::from(b'0' + u.int_in_range(0..=9)?))?;
+        }
+        push(')')?;
+
+        UFixedDouble::parse(&body, length, point as u8).map_err(overflow)
+    }
+}
+
+impl<'a> Arbitrary<'a> for UFixedInteger {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(UFixedInteger(u.arbitrary()?))
+    }
+}
+
+/// Telegrams with a valid header, a selection of objects and a valid CRC.
+impl<'a> Arbitrary<'a> for Readout {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut telegram = heapless::String::<2048>::new();
+
+        telegram.push('/').map_err(overflow)?;
+        for _ in 0..3 {
+            telegram
+                .push(char::from(u.int_in_range(b'A'..=b'Z')?))
+                .map_err(overflow)?;
+        }
+        telegram.push('5').map_err(overflow)?;
+        for _ in 0..u.int_in_range(4..=24)? {
+            telegram
+                .push(char::from(u.int_in_range(b'0'..=b'9')?))
+                .map_err(overflow)?;
+        }
+        telegram.push_str("\r\n\r\n").map_err(overflow)?;
+
+        for _ in 0..u.int_in_range(0..=16)? {
+            let tariff = u.int_in_range(1..=2)?;
+            match u.int_in_range(0..=3)? {
+                0 => {
+                    let tst = TST::arbitrary(u)?;
+                    telegram.push_str("0-0:1.0.0").map_err(overflow)?;
+                    write_tst(&mut telegram, &tst)
+                }
+                1 => write!(
+                    telegram,
+                    "1-0:1.8.{}({:06}.{:03}*kWh)",
+                    tariff,
+                    u.int_in_range(0..=999_999)?,
+                    u.int_in_range(0..=999)?
+                ),
+                2 => write!(
+                    telegram,
+                    "1-0:1.7.0({:02}.{:03}*kW)",
+                    u.int_in_range(0..=99)?,
+                    u.int_in_range(0..=999)?
+                ),
+                _ => write!(telegram, "0-0:96.7.21({:05})", u.int_in_range(0..=99_999)?),
+            }
+            .map_err(overflow)?;
+            telegram.push_str("\r\n").map_err(overflow)?;
+        }
+
+        telegram.push('!').map_err(overflow)?;
+        let crc = crc16::State::<crc16::ARC>::calculate(telegram.as_bytes());
+        write!(telegram, "{:04X}\r\n", crc).map_err(overflow)?;
+
+        let mut buffer = [0u8; 2048];
+        buffer[..telegram.len()].copy_from_slice(telegram.as_bytes());
+        Ok(Readout {
+            buffer,
+            len: telegram.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    /// Deterministic pseudo-random bytes to drive the generators.
+    fn entropy(len: usize) -> std::vec::Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tst_roundtrip() {
+        let bytes = entropy(4096);
+        let mut u = Unstructured::new(&bytes);
+
+        for _ in 0..256 {
+            let tst = crate::types::TST::arbitrary(&mut u).unwrap();
+            let mut body = std::string::String::new();
+            super::write_tst(&mut body, &tst).unwrap();
+            assert_eq!(crate::types::TST::parse(&body).unwrap(), tst);
+        }
+    }
+
+    #[test]
+    fn tst_roundtrip_rfc3339() {
+        let bytes = entropy(4096);
+        let mut u = Unstructured::new(&bytes);
+
+        for _ in 0..256 {
+            let tst = crate::types::TST::arbitrary(&mut u).unwrap();
+            let formatted = format!("{}", tst);
+            let parsed = crate::types::TST::parse_rfc3339(&formatted).unwrap();
+            assert_eq!(
+                (parsed.year, parsed.month, parsed.day, parsed.dst),
+                (tst.year, tst.month, tst.day, tst.dst)
+            );
+            assert_eq!(
+                (parsed.hour, parsed.minute, parsed.second),
+                (tst.hour, tst.minute, tst.second)
+            );
+        }
+    }
+
+    #[test]
+    fn readout() {
+        let bytes = entropy(16384);
+        let mut u = Unstructured::new(&bytes);
+
+        let mut objects = 0;
+        for _ in 0..32 {
+            let readout = crate::Readout::arbitrary(&mut u).unwrap();
+            let telegram = readout.to_telegram().unwrap();
+            assert!(telegram.objects().all(|o| o.is_ok()));
+            objects += telegram.objects().count();
+        }
+        assert!(objects > 32);
+    }
+
+    #[test]
+    fn ufixeddouble_roundtrip() {
+        let bytes = entropy(4096);
+        let mut u = Unstructured::new(&bytes);
+
+        for _ in 0..256 {
+            let value = crate::types::UFixedDouble::arbitrary(&mut u).unwrap();
+            let body = format!("({}*kWh)", value);
+            let parsed = crate::types::UFixedDouble::parse_max(&body, 10).unwrap();
+            assert_eq!(parsed.as_scaled(), value.as_scaled(), "{}", body);
+        }
+    }
+}
@@ -2,9 +2,15 @@
 
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod state;
 pub mod types;
 
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod line_ending;
 mod obis;
 mod reader;
 
@@ -12,76 +18,551 @@ pub use crate::obis::*;
 pub use crate::reader::*;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     InvalidFormat,
     InvalidChecksum,
-    UnknownObis,
+
+    /// Object with an unknown OBIS reference, holding the reference (truncated to 16 characters).
+    UnknownObis(heapless::String<16>),
+}
+
+impl Error {
+    /// Construct an `Error::UnknownObis` for the given reference.
+    pub(crate) fn unknown_obis(reference: &str) -> Error {
+        let mut truncated = heapless::String::new();
+        for c in reference.chars() {
+            if truncated.push(c).is_err() {
+                break;
+            }
+        }
+
+        Error::UnknownObis(truncated)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::InvalidFormat => f.write_str("invalid format"),
+            Error::InvalidChecksum => f.write_str("checksum does not match the telegram"),
+            Error::UnknownObis(reference) => write!(f, "unknown OBIS reference {}", reference),
+        }
+    }
 }
 
+impl core::error::Error for Error {}
+
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Parse a single line holding a COSEM object, for example as copied from a log.
+///
+/// ```
+/// let o = dsmr5::parse_object("1-0:1.7.0(00.193*kW)").unwrap();
+///
+/// if let dsmr5::OBIS::PowerDelivered(p) = o {
+///     assert_eq!(f64::from(&p), 0.193);
+/// }
+/// ```
+pub fn parse_object(line: &str) -> Result<OBIS<'_>> {
+    OBIS::parse(line)
+}
+
+/// Find the start of the next telegram in `bytes` that carries a valid CRC16 checksum.
+///
+/// Unlike naively jumping to the next `/`, this skips stray `/` bytes and corrupted telegrams.
+/// Useful to resynchronise on a misaligned stream or to recover from a damaged capture.
+pub fn find_next_telegram(bytes: &[u8]) -> Option<usize> {
+    (0..bytes.len())
+        .filter(|&i| bytes[i] == b'/')
+        .find(|&start| {
+            let telegram = &bytes[start..];
+            let Some(data_end) = telegram.iter().position(|&b| b == b'!') else {
+                return false;
+            };
+
+            telegram
+                .get(data_end + 1..data_end + 5)
+                .and_then(|crc| core::str::from_utf8(crc).ok())
+                .and_then(|crc| u16::from_str_radix(crc, 16).ok())
+                .is_some_and(|crc| {
+                    crc == crc16::State::<crc16::ARC>::calculate(&telegram[..=data_end])
+                })
+        })
+}
+
 /// A data readout message from the metering system as per section 6.2.
-pub struct Readout {
-    pub buffer: [u8; 2048], // Maximum size of a Readout
+///
+/// Holds up to `N` bytes, which by default suffices for telegrams of a common meter with a few
+/// slave devices. Lower it on memory-constrained targets, or raise it for extensive telegrams.
+pub struct Readout<const N: usize = 2048> {
+    pub buffer: [u8; N], // Maximum size of a Readout
+
+    /// The amount of meaningful bytes at the start of the buffer.
+    pub len: usize,
 }
 
 impl Readout {
+    /// Decode a readout from its hexadecimal representation, as delivered by some transports.
+    pub fn from_hex(s: &str) -> Result<Readout> {
+        let s = s.as_bytes();
+        let mut buffer = [0u8; 2048];
+
+        if !s.len().is_multiple_of(2) || s.len() / 2 > buffer.len() {
+            return Err(Error::InvalidFormat);
+        }
+
+        for (b, pair) in buffer.iter_mut().zip(s.chunks_exact(2)) {
+            let pair = core::str::from_utf8(pair).map_err(|_| Error::InvalidFormat)?;
+            *b = u8::from_str_radix(pair, 16).map_err(|_| Error::InvalidFormat)?;
+        }
+
+        Ok(Readout {
+            buffer,
+            len: s.len() / 2,
+        })
+    }
+
+    /// Decode a readout from its base64 representation, as delivered by some transports.
+    #[cfg(feature = "base64")]
+    pub fn from_base64(s: &str) -> Result<Readout> {
+        use base64::Engine;
+
+        let mut buffer = [0u8; 2048];
+        let len = base64::engine::general_purpose::STANDARD
+            .decode_slice(s, &mut buffer)
+            .map_err(|_| Error::InvalidFormat)?;
+
+        Ok(Readout { buffer, len })
+    }
+}
+
+impl<const N: usize> Readout<N> {
+    /// The meaningful bytes of the buffer, i.e. the telegram as received.
+    pub fn as_telegram_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len.min(N)]
+    }
+
     /// Parse the readout to an actual telegram message.
     ///
     /// Checks the integrity of the telegram by the CRC16 checksum included.
     /// Parses the prefix and identification, and will allow the parsing of the COSEM objects.
+    ///
+    /// Besides CR LF, lone CR or LF line endings are accepted. Note that the checksum is always
+    /// verified over the bytes as received, regardless of the line endings used. See
+    /// `normalized` for a copy in the standard form.
     pub fn to_telegram(&'_ self) -> Result<Telegram<'_>> {
-        let buffer = core::str::from_utf8(&self.buffer).map_err(|_| Error::InvalidFormat)?;
+        self.to_telegram_with(ParseOptions::strict())
+    }
 
-        if buffer.len() < 16 {
-            return Err(Error::InvalidFormat);
-        }
+    /// Parse the readout to an actual telegram message, as tolerant as specified by the options.
+    pub fn to_telegram_with(&'_ self, options: ParseOptions) -> Result<Telegram<'_>> {
+        BorrowedReadout(self.as_telegram_bytes()).to_telegram_with(options)
+    }
+
+    /// Parse the readout to an actual telegram message, without verifying the checksum.
+    ///
+    /// Allows parsing telegrams of meters preceding DSMR 4, which lack the checksum altogether.
+    pub fn to_telegram_unchecked(&'_ self) -> Result<Telegram<'_>> {
+        BorrowedReadout(self.as_telegram_bytes()).to_telegram_unchecked()
+    }
+
+    /// Parse the readout to an actual telegram message, verifying the checksum as per the CRC16
+    /// algorithm `C` instead of the ARC algorithm prescribed by the standard.
+    ///
+    /// Useful for meters with vendor-modified P1 implementations, e.g. `crc16::MODBUS`.
+    pub fn to_telegram_with_crc<C: crc16::CrcType>(&'_ self) -> Result<Telegram<'_>> {
+        BorrowedReadout(self.as_telegram_bytes()).to_telegram_with_crc::<C>()
+    }
+
+    /// A copy of the telegram with all line endings normalised to CR LF, as the standard prescribes.
+    ///
+    /// The checksum is verified over the bytes as received, after which it is recomputed over
+    /// the normalised bytes, such that the copy again parses with `to_telegram`. Parsing does not
+    /// need this, but it is useful to store or forward readouts in the standard form. Fails when
+    /// the readout does not parse, or the copy does not fit `N` bytes.
+    pub fn normalized(&self) -> Result<Readout<N>> {
+        use core::fmt::Write;
+
+        let telegram = self.to_telegram()?;
+
+        let mut buffer = [0u8; N];
+        let len = line_ending::normalize_into(telegram.checksummed_bytes(), &mut buffer)
+            .ok_or(Error::InvalidFormat)?;
 
-        let data_end = buffer.find('!').ok_or(Error::InvalidFormat)?;
-        let (buffer, postfix) = buffer.split_at(data_end + 1);
+        let checksum = crc16::State::<crc16::ARC>::calculate(&buffer[..len]);
+        let mut crc = heapless::String::<8>::new();
+        write!(crc, "{:04X}\r\n", checksum).map_err(|_| Error::InvalidFormat)?;
+        buffer
+            .get_mut(len..len + crc.len())
+            .ok_or(Error::InvalidFormat)?
+            .copy_from_slice(crc.as_bytes());
 
-        let given_checksum = u16::from_str_radix(postfix.get(..4).ok_or(Error::InvalidFormat)?, 16)
+        Ok(Readout {
+            buffer,
+            len: len + crc.len(),
+        })
+    }
+
+    /// Parse only the prefix and identification of the readout.
+    ///
+    /// Does not verify the CRC16 checksum, nor does it validate the rest of the telegram.
+    /// Useful to cheaply route a readout before committing to a full `to_telegram`.
+    pub fn header_only(&self) -> Result<(&str, &str)> {
+        let bytes = self.as_telegram_bytes();
+        let header_start = bytes
+            .iter()
+            .position(|&b| b == b'/')
+            .ok_or(Error::InvalidFormat)?;
+        let header_end = header_start
+            + bytes[header_start..]
+                .iter()
+                .position(|&b| line_ending::is_line_ending(b))
+                .ok_or(Error::InvalidFormat)?;
+        let header = core::str::from_utf8(&bytes[header_start..header_end])
             .map_err(|_| Error::InvalidFormat)?;
-        let real_checksum = crc16::State::<crc16::ARC>::calculate(buffer.as_bytes());
 
-        if given_checksum != real_checksum {
+        parse_header(header)
+    }
+}
+
+/// A data readout message borrowed from existing bytes, such as a capture read from disk.
+///
+/// Parses exactly like `Readout`, without copying the bytes into a buffer first.
+pub struct BorrowedReadout<'a>(pub &'a [u8]);
+
+impl<'a> BorrowedReadout<'a> {
+    /// Parse the readout to an actual telegram message, see `Readout::to_telegram`.
+    pub fn to_telegram(&self) -> Result<Telegram<'a>> {
+        self.to_telegram_with(ParseOptions::strict())
+    }
+
+    /// Parse the readout to an actual telegram message, see `Readout::to_telegram_with`.
+    pub fn to_telegram_with(&self, options: ParseOptions) -> Result<Telegram<'a>> {
+        parse_telegram::<crc16::ARC>(self.0, options)
+    }
+
+    /// Parse the readout to an actual telegram message, see `Readout::to_telegram_unchecked`.
+    pub fn to_telegram_unchecked(&self) -> Result<Telegram<'a>> {
+        self.to_telegram_with(ParseOptions {
+            verify_crc: false,
+            ..ParseOptions::strict()
+        })
+    }
+
+    /// Parse the readout to an actual telegram message, see `Readout::to_telegram_with_crc`.
+    pub fn to_telegram_with_crc<C: crc16::CrcType>(&self) -> Result<Telegram<'a>> {
+        parse_telegram::<C>(self.0, ParseOptions::strict())
+    }
+}
+
+/// Parse a telegram directly from a byte slice of arbitrary length, see `BorrowedReadout`.
+impl<'a> core::convert::TryFrom<&'a [u8]> for Telegram<'a> {
+    type Error = Error;
+
+    fn try_from(buffer: &'a [u8]) -> Result<Self> {
+        BorrowedReadout(buffer).to_telegram()
+    }
+}
+
+/// Options controlling how tolerant parsing is.
+///
+/// Used with `Readout::to_telegram_with`, which only regards `verify_crc`, and with
+/// `Telegram::objects_with` and `OBIS::parse_with`, which regard the others.
+///
+/// The reference normalisation of the `lenient` feature applies regardless of these options,
+/// as it is meant to be opted into for a whole build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Verify the CRC16 checksum of the telegram.
+    ///
+    /// When disabled, the checksum may also be absent altogether.
+    pub verify_crc: bool,
+
+    /// Yield objects with an unknown OBIS reference as `OBIS::Unknown`,
+    /// instead of as `Error::UnknownObis`.
+    pub unknown_as_raw: bool,
+
+    /// Tolerate a missing or unexpected unit on objects whose unit is validated,
+    /// such as the fuse supervision threshold and slave meter readings.
+    ///
+    /// Slave meter readings with an unknown unit then yield no unit.
+    pub lenient_units: bool,
+
+    /// Accept timestamps lacking the daylight savings indicator, see `TST::parse_lenient`.
+    pub lenient_tst: bool,
+}
+
+impl ParseOptions {
+    /// Parse exactly as prescribed by the standard.
+    pub const fn strict() -> Self {
+        ParseOptions {
+            verify_crc: true,
+            unknown_as_raw: false,
+            lenient_units: false,
+            lenient_tst: false,
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::strict()
+    }
+}
+
+/// Parse a buffer holding a readout to a telegram, see `Readout::to_telegram`.
+///
+/// The checksum is verified as per the CRC16 algorithm `C`, which the standard prescribes as ARC.
+fn parse_telegram<C: crc16::CrcType>(buffer: &[u8], options: ParseOptions) -> Result<Telegram<'_>> {
+    // Strip anything preceding the telegram, like a byte order mark or whitespace.
+    let telegram_start = buffer
+        .iter()
+        .position(|&b| b == b'/')
+        .ok_or(Error::InvalidFormat)?;
+    let buffer = &buffer[telegram_start..];
+
+    // Only the telegram itself is validated, as the buffer may be followed by stale bytes.
+    let data_end = buffer
+        .iter()
+        .position(|&b| b == b'!')
+        .ok_or(Error::InvalidFormat)?;
+    let given_checksum = buffer
+        .get(data_end + 1..data_end + 5)
+        .and_then(|crc| core::str::from_utf8(crc).ok())
+        .and_then(|crc| u16::from_str_radix(crc, 16).ok());
+    let telegram_end = data_end + 1 + given_checksum.map_or(0, |_| 4);
+
+    let full_buffer =
+        core::str::from_utf8(&buffer[..telegram_end]).map_err(|_| Error::InvalidFormat)?;
+    if full_buffer.len() < 16 {
+        return Err(Error::InvalidFormat);
+    }
+
+    let buffer = &full_buffer[..=data_end];
+
+    if options.verify_crc {
+        let real_checksum = crc16::State::<C>::calculate(buffer.as_bytes());
+        if given_checksum.ok_or(Error::InvalidFormat)? != real_checksum {
             return Err(Error::InvalidChecksum);
         }
+    }
+
+    let (header, data) = line_ending::split_line(buffer);
+    if data.is_empty() {
+        return Err(Error::InvalidFormat);
+    }
 
-        let data_start = buffer.find("\r\n\r\n").ok_or(Error::InvalidFormat)?;
-        let (header, data) = buffer.split_at(data_start);
+    let (prefix, identification) = parse_header(header)?;
+    let object_buffer = line_ending::trim(data.strip_suffix('!').ok_or(Error::InvalidFormat)?);
 
-        let prefix = header.get(1..4).ok_or(Error::InvalidFormat)?;
-        let identification = header.get(5..).ok_or(Error::InvalidFormat)?;
+    Ok(Telegram {
+        checksum: given_checksum.unwrap_or(0),
+        crc_verified: options.verify_crc,
+        prefix,
+        identification,
+        telegram_buffer: full_buffer,
+        object_buffer,
+    })
+}
 
-        Ok(Telegram {
-            checksum: given_checksum,
-            prefix,
-            identification,
-            object_buffer: data.get(4..data.len() - 3).ok_or(Error::InvalidFormat)?,
-        })
+/// A data readout message backed by a growable buffer, for telegrams of arbitrary size.
+#[cfg(feature = "alloc")]
+pub struct ReadoutVec {
+    pub buffer: alloc::vec::Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl ReadoutVec {
+    /// Parse the readout to an actual telegram message, see `Readout::to_telegram`.
+    pub fn to_telegram(&'_ self) -> Result<Telegram<'_>> {
+        BorrowedReadout(&self.buffer).to_telegram()
     }
 }
 
+/// Split a telegram header line into its prefix and identification.
+fn parse_header(header: &str) -> Result<(&str, &str)> {
+    let prefix = header.get(1..4).ok_or(Error::InvalidFormat)?;
+    let identification = header.get(5..).ok_or(Error::InvalidFormat)?;
+
+    Ok((prefix, identification))
+}
+
 /// A P1 telegram from the metering system as per section 6.12.
 pub struct Telegram<'a> {
-    /// The verified CRC16 checksum of the telegram data.
+    /// The CRC16 checksum of the telegram data.
+    ///
+    /// Verified unless disabled by `ParseOptions`, in which case it is 0 if absent.
     pub checksum: u16,
 
+    /// Whether the checksum was verified, see `crc_verified`.
+    crc_verified: bool,
+
     /// The first 3 characters of the datagram.
     pub prefix: &'a str,
 
     /// Metering system identification.
     pub identification: &'a str,
 
+    /// String buffer representing the entire telegram, from `/` up to and including the CRC.
+    telegram_buffer: &'a str,
+
     /// String buffer representing the COSEM objects.
     object_buffer: &'a str,
 }
 
 impl<'a> Telegram<'a> {
+    /// The model of the meter, being the identification without the enhanced identification
+    /// (i.e. `\` followed by a character) some meters prefix it with.
+    ///
+    /// Note that the format of the model is not defined in the requirements and vendor-specific.
+    pub fn model(&self) -> &'a str {
+        match self.identification.strip_prefix('\\') {
+            Some(model) => model.get(1..).unwrap_or(""),
+            None => self.identification,
+        }
+    }
+
+    /// The baud rate identification character, directly following the prefix in the header.
+    ///
+    /// As per IEC 62056-21 `0` through `6` denote 300, 600, 1200, 2400, 4800, 9600 and 19200
+    /// baud respectively. DSMR meters commonly emit `5` while their P1 port runs at 115200 baud,
+    /// so treat it as informative only.
+    pub fn baud_identifier(&self) -> Option<char> {
+        self.telegram_buffer.chars().nth(4)
+    }
+
+    /// Whether the checksum was present and verified to match the telegram.
+    ///
+    /// Only `false` when verification was disabled by `ParseOptions`, in which case the
+    /// checksum may not be trusted or be absent altogether.
+    pub fn crc_verified(&self) -> bool {
+        self.crc_verified
+    }
+
+    /// The exact bytes of the telegram, from the leading `/` up to and including the CRC.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.telegram_buffer.as_bytes()
+    }
+
+    /// The bytes the CRC16 checksum is computed over, from the leading `/` up to and including
+    /// the terminating `!`.
+    pub fn checksummed_bytes(&self) -> &'a [u8] {
+        // The checksum consists of hexadecimal digits, thus the last `!` terminates the data.
+        let end = self
+            .telegram_buffer
+            .rfind('!')
+            .map_or(self.telegram_buffer.len(), |i| i + 1);
+        &self.telegram_buffer.as_bytes()[..end]
+    }
+
     /// Parse the COSEM objects, yielding them as part of an iterator.
     pub fn objects(&self) -> impl core::iter::Iterator<Item = Result<OBIS<'a>>> {
-        self.object_buffer.lines().map(OBIS::parse)
+        self.objects_with(ParseOptions::strict())
+    }
+
+    /// Parse the COSEM objects as tolerant as specified by the options.
+    pub fn objects_with(
+        &self,
+        options: ParseOptions,
+    ) -> impl core::iter::Iterator<Item = Result<OBIS<'a>>> {
+        self.lines()
+            .map(move |line| match OBIS::parse_with(line, options) {
+                Err(Error::UnknownObis(_)) if options.unknown_as_raw => {
+                    let (reference, body) = obis::split_reference(line)?;
+                    Ok(OBIS::Unknown(reference, body))
+                }
+                o => o,
+            })
+    }
+
+    /// Parse the COSEM objects, skipping those with an unknown OBIS reference.
+    ///
+    /// Other errors, such as malformed values of known objects, are still yielded.
+    pub fn known_objects(&self) -> impl core::iter::Iterator<Item = Result<OBIS<'a>>> {
+        self.objects()
+            .filter(|o| !matches!(o, Err(Error::UnknownObis(_))))
+    }
+
+    /// Split the COSEM objects into their OBIS reference and raw body, without parsing them.
+    ///
+    /// The body includes its parentheses, e.g. `("1-0:1.7.0", "(00.193*kW)")`. Useful to log or
+    /// route objects this crate does not model.
+    pub fn raw_objects(&self) -> impl core::iter::Iterator<Item = Result<(&'a str, &'a str)>> {
+        self.lines().map(obis::split_reference)
+    }
+
+    /// Parse only the first COSEM object with the given OBIS reference, e.g. `1-0:1.7.0`.
+    ///
+    /// Yields `None` if the telegram does not hold such an object.
+    pub fn find_object(&self, reference: &str) -> Option<Result<OBIS<'a>>> {
+        self.lines()
+            .find(|line| {
+                line.strip_prefix(reference)
+                    .is_some_and(|body| body.starts_with('('))
+            })
+            .map(OBIS::parse)
+    }
+
+    /// The lines of the object buffer, each representing a COSEM object.
+    ///
+    /// Lines starting with `(` continue the object on the preceding line, as do the values of
+    /// the DSMR 3 `24.3.0` gas readings. These are joined to that object, line endings included.
+    fn lines(&self) -> impl core::iter::Iterator<Item = &'a str> {
+        let mut rest = self.object_buffer;
+        core::iter::from_fn(move || {
+            rest = line_ending::trim_start(rest);
+            if rest.is_empty() {
+                return None;
+            }
+
+            let mut end = line_ending::split_line(rest).0.len();
+            loop {
+                let next = line_ending::trim_start(&rest[end..]);
+                if !next.starts_with('(') {
+                    break;
+                }
+                end = rest.len() - next.len() + line_ending::split_line(next).0.len();
+            }
+
+            let (line, tail) = rest.split_at(end);
+            rest = tail;
+            Some(line)
+        })
+    }
+
+    /// Parse every timestamp in the telegram, in order of appearance.
+    ///
+    /// Besides the time of the telegram these are the capture times of slave readings and the
+    /// times of logged events, which helps verifying the clocks of the meters agree.
+    pub fn timestamps(&self) -> impl core::iter::Iterator<Item = Result<types::TST>> + 'a {
+        self.objects().flat_map(|o| {
+            let (object, error) = match o {
+                Ok(o) => (Some(o), None),
+                Err(e) => (None, Some(Err(e))),
+            };
+            object
+                .into_iter()
+                .flat_map(OBIS::into_timestamps)
+                .chain(error)
+        })
+    }
+
+    /// Parse the COSEM objects, each paired with the part of the installation it pertains to.
+    pub fn scoped_objects(&self) -> impl core::iter::Iterator<Item = Result<(Scope, OBIS<'a>)>> {
+        self.objects().map(|o| o.map(|o| (o.scope(), o)))
+    }
+
+    /// Convert the telegram header and its surmised state to a JSON object.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self) -> Result<serde_json::Value> {
+        let state = state::State::try_from(self)?;
+
+        Ok(serde_json::json!({
+            "prefix": self.prefix,
+            "identification": self.identification,
+            "state": state.to_json_value(),
+        }))
     }
 }
 
@@ -99,11 +580,16 @@ mod tests {
         let (left, _right) = buffer.split_at_mut(file.len());
         left.copy_from_slice(file.as_slice());
 
-        let readout = crate::Readout { buffer };
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
         let telegram = readout.to_telegram().unwrap();
 
         assert_eq!(telegram.prefix, "ISK");
         assert_eq!(telegram.identification, "\\2M550E-1012");
+        assert_eq!(telegram.model(), "M550E-1012");
+        assert_eq!(telegram.baud_identifier(), Some('5'));
 
         telegram.objects().for_each(|o| {
             println!("{:?}", o); // to see use `$ cargo test -- --nocapture`
@@ -160,11 +646,16 @@ mod tests {
         let (left, _right) = buffer.split_at_mut(file.len());
         left.copy_from_slice(file.as_slice());
 
-        let readout = crate::Readout { buffer };
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
         let telegram = readout.to_telegram().unwrap();
 
         assert_eq!(telegram.prefix, "KFM");
         assert_eq!(telegram.identification, "KAIFA-METER");
+        assert_eq!(telegram.model(), "KAIFA-METER");
+        assert_eq!(telegram.baud_identifier(), Some('5'));
 
         telegram.objects().for_each(|o| {
             println!("{:?}", o); // to see use `$ cargo test -- --nocapture`
@@ -213,6 +704,587 @@ mod tests {
         });
     }
 
+    #[test]
+    fn header_only() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/kaifa.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        // Corrupt the checksum, which should not matter for the header.
+        let crc = file.iter().position(|&b| b == b'!').unwrap() + 1;
+        buffer[crc] = b'0';
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        assert!(matches!(
+            readout.to_telegram(),
+            Err(crate::Error::InvalidChecksum)
+        ));
+        assert_eq!(readout.header_only().unwrap(), ("KFM", "KAIFA-METER"));
+    }
+
+    #[test]
+    fn as_bytes() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/isk.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = readout.to_telegram().unwrap();
+
+        // The fixture carries a trailing line ending after the CRC.
+        assert_eq!(telegram.as_bytes(), &file[..file.len() - 4]);
+        assert!(telegram.as_bytes().ends_with(b"!67B1"));
+    }
+
+    #[test]
+    fn leading_bytes() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/bom.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = readout.to_telegram().unwrap();
+
+        assert_eq!(telegram.prefix, "ISK");
+        assert_eq!(telegram.identification, "\\2M550E-1012");
+        assert_eq!(readout.header_only().unwrap(), ("ISK", "\\2M550E-1012"));
+        assert!(telegram.as_bytes().starts_with(b"/ISK5"));
+        assert_eq!(telegram.objects().count(), 23);
+    }
+
+    #[test]
+    fn header_only_leading_line_ending() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/isk.txt").unwrap();
+
+        buffer[..2].copy_from_slice(b"\r\n");
+        buffer[2..file.len() + 2].copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len() + 2,
+        };
+        assert!(readout.to_telegram().is_ok());
+        assert_eq!(readout.header_only().unwrap(), ("ISK", "\\2M550E-1012"));
+    }
+
+    #[test]
+    fn scoped_objects() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/kaifa.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = readout.to_telegram().unwrap();
+
+        use crate::Scope;
+        let scopes: std::vec::Vec<Scope> =
+            telegram.scoped_objects().map(|o| o.unwrap().0).collect();
+
+        let count = |scope| scopes.iter().filter(|&&s| s == scope).count();
+        assert_eq!(count(Scope::Common), 15);
+        assert_eq!(count(Scope::Line(crate::Line::Line1)), 5);
+        assert_eq!(count(Scope::Line(crate::Line::Line2)), 5);
+        assert_eq!(count(Scope::Line(crate::Line::Line3)), 5);
+        assert_eq!(count(Scope::Slave(crate::Slave::Slave1)), 3);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn oversized() {
+        use std::io::Read;
+
+        let file = std::fs::read("test/oversized.txt").unwrap();
+        assert!(file.len() > 2048);
+
+        let bytes = std::io::BufReader::new(file.as_slice()).bytes();
+        let mut reader = crate::Reader::new(bytes);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(crate::ReaderError::BufferOverFlow { .. }))
+        ));
+
+        let readout = crate::ReadoutVec { buffer: file };
+        let telegram = readout.to_telegram().unwrap();
+        assert_eq!(telegram.prefix, "ISK");
+        assert_eq!(telegram.objects().count(), 23);
+    }
+
+    #[test]
+    fn borrowed_readout() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        assert_eq!(telegram.prefix, "ISK");
+        assert_eq!(telegram.identification, "\\2M550E-1012");
+        assert_eq!(telegram.objects().count(), 23);
+
+        let mut buffer = [0u8; 2048];
+        buffer[..file.len()].copy_from_slice(file.as_slice());
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let owned = readout.to_telegram().unwrap();
+        assert_eq!(owned.prefix, telegram.prefix);
+        assert_eq!(owned.identification, telegram.identification);
+        assert_eq!(owned.checksum, telegram.checksum);
+        assert!(telegram.crc_verified());
+        assert!(owned.crc_verified());
+    }
+
+    #[test]
+    fn try_from_slice() {
+        let file: std::vec::Vec<u8> = std::fs::read("test/flu.txt").unwrap();
+        let telegram = crate::Telegram::try_from(file.as_slice()).unwrap();
+        assert_eq!(telegram.prefix, "FLU");
+        assert!(telegram.objects().all(|o| o.is_ok()));
+
+        assert!(matches!(
+            crate::Telegram::try_from(&file[..file.len() - 8]),
+            Err(crate::Error::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn checksummed_bytes() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+
+        let bytes = telegram.checksummed_bytes();
+        assert!(bytes.starts_with(b"/ISK5"));
+        assert!(bytes.ends_with(b"!"));
+        assert_eq!(
+            crc16::State::<crc16::ARC>::calculate(bytes),
+            telegram.checksum
+        );
+
+        let file = std::fs::read("test/nocrc.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file)
+            .to_telegram_unchecked()
+            .unwrap();
+        assert_eq!(telegram.checksummed_bytes(), telegram.as_bytes());
+    }
+
+    #[test]
+    fn timestamps() {
+        use crate::types::TST;
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+
+        let timestamps: std::vec::Vec<TST> = telegram.timestamps().map(|t| t.unwrap()).collect();
+        assert_eq!(timestamps.len(), 8);
+        assert_eq!(timestamps[0], TST::parse("(190320181403W)").unwrap());
+        assert_eq!(timestamps[1], TST::parse("(190201235231W)").unwrap());
+        assert_eq!(timestamps[7], TST::parse("(190320181003W)").unwrap());
+
+        let file = std::fs::read("test/flu.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        // The telegram, the peak of this month, the start and peak of 3 months and the gas reading.
+        assert_eq!(telegram.timestamps().count(), 9);
+        assert!(telegram.timestamps().all(|t| t.is_ok()));
+    }
+
+    #[test]
+    fn raw_objects() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+
+        let references: std::vec::Vec<_> = telegram.raw_objects().map(|o| o.unwrap().0).collect();
+        assert_eq!(references.len(), 23);
+        assert_eq!(references[0], "1-3:0.2.8");
+        assert!(references.contains(&"1-0:1.7.0"));
+        assert!(references.contains(&"0-1:24.2.1"));
+
+        let (_, body) = telegram
+            .raw_objects()
+            .map(|o| o.unwrap())
+            .find(|(reference, _)| *reference == "1-0:1.7.0")
+            .unwrap();
+        assert_eq!(body, "(00.193*kW)");
+    }
+
+    #[test]
+    fn stale_bytes() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+
+        // A reused buffer, of which only the start is overwritten by a new telegram.
+        let mut buffer = [0xffu8; 2048];
+        buffer[..file.len()].copy_from_slice(file.as_slice());
+        buffer[file.len()..file.len() + 8].copy_from_slice(b"/KFM5!00");
+
+        let readout = crate::Readout { buffer, len: 2048 };
+        let telegram = readout.to_telegram().unwrap();
+        assert_eq!(telegram.prefix, "ISK");
+        assert_eq!(telegram.objects().count(), 23);
+        assert!(telegram.as_bytes().ends_with(b"!67B1"));
+    }
+
+    #[test]
+    fn crc_algorithm() {
+        let file = std::fs::read("test/short.txt").unwrap();
+        let data_end = file.iter().position(|&b| b == b'!').unwrap() + 1;
+
+        let mut buffer = [0u8; 2048];
+        buffer[..data_end].copy_from_slice(&file[..data_end]);
+        let crc = crc16::State::<crc16::MODBUS>::calculate(&file[..data_end]);
+        buffer[data_end..data_end + 4].copy_from_slice(format!("{:04X}", crc).as_bytes());
+
+        let readout = crate::Readout {
+            buffer,
+            len: data_end + 4,
+        };
+        let telegram = readout.to_telegram_with_crc::<crc16::MODBUS>().unwrap();
+        assert_eq!(telegram.checksum, crc);
+        assert!(matches!(
+            readout.to_telegram(),
+            Err(crate::Error::InvalidChecksum)
+        ));
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        assert!(matches!(
+            crate::BorrowedReadout(&file).to_telegram_with_crc::<crc16::MODBUS>(),
+            Err(crate::Error::InvalidChecksum)
+        ));
+        assert!(crate::BorrowedReadout(&file)
+            .to_telegram_with_crc::<crc16::ARC>()
+            .is_ok());
+    }
+
+    #[test]
+    fn without_crc() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/nocrc.txt").unwrap();
+        buffer[..file.len()].copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        assert!(matches!(
+            readout.to_telegram(),
+            Err(crate::Error::InvalidFormat)
+        ));
+
+        let telegram = readout.to_telegram_unchecked().unwrap();
+        assert_eq!(telegram.prefix, "ISk");
+        assert_eq!(telegram.identification, "\\2ME382-1003");
+        assert_eq!(telegram.checksum, 0);
+        assert!(!telegram.crc_verified());
+        assert!(telegram.as_bytes().ends_with(b"!"));
+        assert_eq!(telegram.objects().count(), 8);
+        assert!(telegram.objects().all(|o| o.is_ok()));
+    }
+
+    #[test]
+    fn water_meter() {
+        let file = std::fs::read("test/water.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+
+        let readings: std::vec::Vec<_> = telegram
+            .objects()
+            .filter_map(|o| match o.unwrap() {
+                crate::OBIS::SlaveMeterReading(slave, _tst, Some(mr), unit) => {
+                    Some((slave, f64::from(&mr), unit))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            readings,
+            [
+                (
+                    crate::Slave::Slave1,
+                    304.089,
+                    Some(crate::types::Unit::CubicMeter)
+                ),
+                (
+                    crate::Slave::Slave2,
+                    12.345,
+                    Some(crate::types::Unit::CubicMeter)
+                ),
+            ]
+        );
+
+        let heat = crate::parse_object("0-3:24.2.1(190320181000W)(00012.345*GJ)").unwrap();
+        assert!(matches!(
+            heat,
+            crate::OBIS::SlaveMeterReading(
+                crate::Slave::Slave3,
+                _,
+                Some(_),
+                Some(crate::types::Unit::Gigajoule)
+            )
+        ));
+    }
+
+    #[test]
+    fn find_object() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+
+        let power = telegram.find_object("1-0:1.7.0").unwrap().unwrap();
+        assert!(matches!(power, crate::OBIS::PowerDelivered(p) if f64::from(&p) == 0.193));
+
+        // Only exact references match, rather than any with the same prefix.
+        assert!(telegram.find_object("0-0:96.7.2").is_none());
+        assert!(matches!(
+            telegram.find_object("0-0:96.7.21"),
+            Some(Ok(crate::OBIS::PowerFailures(_)))
+        ));
+        assert!(telegram.find_object("1-0:1.6.0").is_none());
+    }
+
+    #[test]
+    fn known_objects() {
+        let file = std::fs::read_to_string("test/short.txt").unwrap();
+        let file = file.replace("1-3:0.2.8(50)", "1-9:0.2.8(50)");
+        let telegram = crate::BorrowedReadout(file.as_bytes())
+            .to_telegram_unchecked()
+            .unwrap();
+
+        assert!(matches!(
+            telegram.objects().next(),
+            Some(Err(crate::Error::UnknownObis(_)))
+        ));
+        assert!(matches!(
+            telegram.known_objects().next(),
+            Some(Ok(crate::OBIS::DateTime(_)))
+        ));
+        assert_eq!(telegram.known_objects().count(), 7);
+        assert!(telegram.known_objects().all(|o| o.is_ok()));
+
+        let file = file.replace("1-0:1.7.0(00.193*kW)", "1-0:1.7.0(00.1X3*kW)");
+        let telegram = crate::BorrowedReadout(file.as_bytes())
+            .to_telegram_unchecked()
+            .unwrap();
+        let errors: std::vec::Vec<_> = telegram.known_objects().filter_map(|o| o.err()).collect();
+        assert!(matches!(errors[..], [crate::Error::InvalidFormat]));
+    }
+
+    #[test]
+    fn error_display() {
+        let display = |e: crate::Error| format!("{}", e);
+
+        assert_eq!(display(crate::Error::InvalidFormat), "invalid format");
+        assert_eq!(
+            display(crate::Error::InvalidChecksum),
+            "checksum does not match the telegram"
+        );
+        assert_eq!(
+            display(crate::Error::unknown_obis("1-0:1.6.0")),
+            "unknown OBIS reference 1-0:1.6.0"
+        );
+
+        let e: &dyn core::error::Error = &crate::Error::InvalidFormat;
+        assert!(e.source().is_none());
+    }
+
+    #[test]
+    fn unknown_obis() {
+        let reference = |line| match crate::parse_object(line) {
+            Err(crate::Error::UnknownObis(reference)) => reference,
+            o => panic!("unexpected {:?}", o),
+        };
+
+        assert_eq!(reference("9-9:9.9.9(1)"), "9-9:9.9.9");
+        assert_eq!(reference("0-1:24.9.9(1)"), "0-1:24.9.9");
+        assert_eq!(reference("255-255:255.255.255(1)"), "255-255:255.255.");
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_format() {
+        fn format<T: defmt::Format>() {}
+
+        format::<crate::Error>();
+        format::<crate::ReaderError<crate::Error>>();
+        format::<crate::OBIS<'static>>();
+        format::<crate::Scope>();
+        format::<crate::types::TST>();
+        format::<crate::types::UFixedDouble>();
+        format::<crate::types::UFixedInteger>();
+    }
+
+    #[test]
+    fn sized_readout() {
+        let mut buffer = [0u8; 512];
+        let file = std::fs::read("test/short.txt").unwrap();
+        buffer[..file.len()].copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout::<512> {
+            buffer,
+            len: file.len(),
+        };
+        assert_eq!(readout.to_telegram().unwrap().objects().count(), 8);
+
+        let mut buffer = [0u8; 4096];
+        let file = std::fs::read("test/oversized.txt").unwrap();
+        buffer[..file.len()].copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout::<4096> {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = readout.to_telegram().unwrap();
+        assert_eq!(telegram.prefix, "ISK");
+        assert_eq!(telegram.objects().count(), 23);
+    }
+
+    #[test]
+    fn line_endings() {
+        for fixture in ["test/isk_cr.txt", "test/isk_lf.txt"] {
+            let mut buffer = [0u8; 2048];
+            let file = std::fs::read(fixture).unwrap();
+
+            let (left, _right) = buffer.split_at_mut(file.len());
+            left.copy_from_slice(file.as_slice());
+
+            let readout = crate::Readout {
+                buffer,
+                len: file.len(),
+            };
+            let telegram = readout.to_telegram().unwrap();
+
+            assert_eq!(telegram.prefix, "ISK");
+            assert_eq!(telegram.identification, "\\2M550E-1012");
+            assert_eq!(readout.header_only().unwrap(), ("ISK", "\\2M550E-1012"));
+            assert_eq!(telegram.objects().count(), 23);
+            assert!(telegram.objects().all(|o| o.is_ok()));
+
+            // Normalised, the telegram equals the one ending its lines in CR LF.
+            let normalized = readout.normalized().unwrap();
+            let expected = std::fs::read("test/isk.txt").unwrap();
+            assert_eq!(
+                normalized.to_telegram().unwrap().as_bytes(),
+                crate::BorrowedReadout(&expected)
+                    .to_telegram()
+                    .unwrap()
+                    .as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn find_next_telegram() {
+        let isk = std::fs::read("test/isk.txt").unwrap();
+        let kaifa = std::fs::read("test/kaifa.txt").unwrap();
+
+        let mut corrupted = isk.clone();
+        corrupted[100] = b'X';
+
+        let mut bytes = b"junk / more junk".to_vec();
+        bytes.extend_from_slice(&corrupted);
+        let isk_start = bytes.len() + 3;
+        bytes.extend_from_slice(b"!!/");
+        bytes.extend_from_slice(&isk);
+        let kaifa_start = bytes.len() + 4;
+        bytes.extend_from_slice(b"junk");
+        bytes.extend_from_slice(&kaifa);
+
+        assert_eq!(crate::find_next_telegram(&bytes), Some(isk_start));
+        assert_eq!(
+            crate::find_next_telegram(&bytes[isk_start + 1..]),
+            Some(kaifa_start - isk_start - 1)
+        );
+        assert_eq!(crate::find_next_telegram(&bytes[kaifa_start + 1..]), None);
+    }
+
+    #[test]
+    fn from_hex() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let hex: std::string::String = file.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let readout = crate::Readout::from_hex(&hex).unwrap();
+        assert_eq!(&readout.buffer[..file.len()], file.as_slice());
+        assert_eq!(readout.to_telegram().unwrap().prefix, "ISK");
+
+        assert!(crate::Readout::from_hex(&hex[1..]).is_err());
+        assert!(crate::Readout::from_hex("0g").is_err());
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn from_base64() {
+        use base64::Engine;
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let mut encoded = [0u8; 2048];
+        let len = base64::engine::general_purpose::STANDARD
+            .encode_slice(&file, &mut encoded)
+            .unwrap();
+        let encoded = std::str::from_utf8(&encoded[..len]).unwrap();
+
+        let readout = crate::Readout::from_base64(encoded).unwrap();
+        assert_eq!(&readout.buffer[..file.len()], file.as_slice());
+        assert_eq!(readout.to_telegram().unwrap().prefix, "ISK");
+
+        assert!(crate::Readout::from_base64("not base64!").is_err());
+    }
+
+    #[test]
+    fn parse_options() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/isk.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        // Corrupt the checksum and introduce an unknown object.
+        let crc = file.iter().position(|&b| b == b'!').unwrap() + 1;
+        buffer[crc] = b'0';
+        let version = file.windows(9).position(|w| w == b"1-3:0.2.8").unwrap();
+        buffer[version + 2] = b'9';
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        assert!(matches!(
+            readout.to_telegram(),
+            Err(crate::Error::InvalidChecksum)
+        ));
+
+        let options = crate::ParseOptions {
+            verify_crc: false,
+            unknown_as_raw: true,
+            ..crate::ParseOptions::strict()
+        };
+        let telegram = readout.to_telegram_with(options).unwrap();
+        assert_eq!(telegram.checksum, 0x07B1);
+        assert!(!telegram.crc_verified());
+
+        assert!(matches!(
+            telegram.objects().next(),
+            Some(Err(crate::Error::UnknownObis(_)))
+        ));
+        assert!(matches!(
+            telegram.objects_with(options).next(),
+            Some(Ok(crate::OBIS::Unknown("1-9:0.2.8", "(50)")))
+        ));
+    }
+
     #[test]
     fn example_mcs() {
         let mut buffer = [0u8; 2048];
@@ -221,7 +1293,10 @@ mod tests {
         let (left, _right) = buffer.split_at_mut(file.len());
         left.copy_from_slice(file.as_slice());
 
-        let readout = crate::Readout { buffer };
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
         let telegram = readout.to_telegram().unwrap();
 
         assert_eq!(telegram.prefix, "MCS");
@@ -238,10 +1313,11 @@ mod tests {
                     assert_eq!(b, [80]);
                 }
                 SlaveDeviceType(_slave, devide_type) => {
-                    assert_eq!(devide_type.is_none(), true);
+                    assert!(devide_type.is_none());
                 }
-                SlaveMeterReading(_slave, _tst, value) => {
-                    assert_eq!(value.is_none(), true)
+                SlaveMeterReading(_slave, _tst, value, unit) => {
+                    assert!(value.is_none());
+                    assert!(unit.is_none());
                 }
                 _ => (), // Do not test the rest.
             }
@@ -0,0 +1,91 @@
+//! Tolerance of line endings, as applied throughout parsing.
+//!
+//! The standard prescribes CR LF, but some serial adaptors and loggers strip or replace either.
+//! All parsing goes through these helpers, such that lone CR or LF line endings are treated alike
+//! everywhere. The telegram is parsed in place rather than from a normalised copy, as the parsed
+//! objects borrow from the buffer as received. See `Readout::normalized` for such a copy.
+
+/// Characters accepted as line endings.
+pub(crate) const LINE_ENDINGS: [char; 2] = ['\r', '\n'];
+
+/// Whether the byte is accepted as (part of) a line ending.
+pub(crate) fn is_line_ending(b: u8) -> bool {
+    b == b'\r' || b == b'\n'
+}
+
+/// Split off the first line, yielding it and the rest starting with its line ending.
+pub(crate) fn split_line(s: &str) -> (&str, &str) {
+    s.split_at(s.find(LINE_ENDINGS).unwrap_or(s.len()))
+}
+
+/// Strip any amount of line endings from the start.
+pub(crate) fn trim_start(s: &str) -> &str {
+    s.trim_start_matches(LINE_ENDINGS)
+}
+
+/// Strip any amount of line endings from both ends.
+pub(crate) fn trim(s: &str) -> &str {
+    s.trim_matches(LINE_ENDINGS)
+}
+
+/// Whether the string holds a line ending.
+pub(crate) fn contains(s: &str) -> bool {
+    s.contains(LINE_ENDINGS)
+}
+
+/// Copy `src` into `dst`, replacing lone CR or LF line endings by CR LF.
+///
+/// Yields the amount of bytes written, or `None` if these do not fit `dst`.
+pub(crate) fn normalize_into(src: &[u8], dst: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    let mut push = |b: u8| {
+        *dst.get_mut(len)? = b;
+        len += 1;
+        Some(len)
+    };
+
+    let mut i = 0;
+    while i < src.len() {
+        if is_line_ending(src[i]) {
+            push(b'\r')?;
+            push(b'\n')?;
+            i += if src[i..].starts_with(b"\r\n") { 2 } else { 1 };
+        } else {
+            push(src[i])?;
+            i += 1;
+        }
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_into() {
+        let normalize = |src: &[u8]| {
+            let mut dst = [0u8; 32];
+            let len = super::normalize_into(src, &mut dst).unwrap();
+            std::vec::Vec::from(&dst[..len])
+        };
+
+        assert_eq!(normalize(b"a\r\nb"), b"a\r\nb");
+        assert_eq!(normalize(b"a\rb\nc"), b"a\r\nb\r\nc");
+        assert_eq!(normalize(b"a\n\nb"), b"a\r\n\r\nb");
+        assert_eq!(normalize(b"a\r\rb"), b"a\r\n\r\nb");
+
+        let mut dst = [0u8; 4];
+        assert_eq!(super::normalize_into(b"a\rb", &mut dst), Some(4));
+        assert_eq!(super::normalize_into(b"a\rbc", &mut dst), None);
+    }
+
+    #[test]
+    fn split_line() {
+        assert_eq!(super::split_line("a\rb"), ("a", "\rb"));
+        assert_eq!(super::split_line("a\nb"), ("a", "\nb"));
+        assert_eq!(super::split_line("ab"), ("ab", ""));
+        assert_eq!(trim_start("\r\n\nab"), "ab");
+        assert!(contains("a\rb") && !contains("ab"));
+    }
+}
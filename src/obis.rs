@@ -1,15 +1,19 @@
+use serde::Serialize;
+
 use crate::types::*;
-use crate::{Error, Result};
+use crate::{Error, ParseOptions, Result};
 
 /// One of two tariffs used by the meter.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Tariff {
     Tariff1 = 0,
     Tariff2 = 1,
 }
 
 /// One of up to three powerlines connected to the meter.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Line {
     Line1 = 0,
     Line2 = 1,
@@ -17,7 +21,8 @@ pub enum Line {
 }
 
 /// On of up to four slave meters connected to the meter.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Slave {
     Slave1 = 0,
     Slave2 = 1,
@@ -25,10 +30,236 @@ pub enum Slave {
     Slave4 = 3,
 }
 
+/// Position of the breaker in remotely controllable (e.g. prepaid) meters.
+///
+/// As per the e-MUCS coding of the breaker state object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SwitchPosition {
+    Disconnected = 0,
+    Connected = 1,
+    ReadyForReconnection = 2,
+}
+
+impl core::convert::TryFrom<u64> for SwitchPosition {
+    type Error = Error;
+
+    fn try_from(code: u64) -> Result<Self> {
+        match code {
+            0 => Ok(SwitchPosition::Disconnected),
+            1 => Ok(SwitchPosition::Connected),
+            2 => Ok(SwitchPosition::ReadyForReconnection),
+            _ => Err(Error::InvalidFormat),
+        }
+    }
+}
+
+/// Log of long power failures, yielding the end time and the duration of each failure.
+///
+/// Note that some meters pad the log with failures at the all-zeroes timestamp.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerFailureEventLog<'a> {
+    events: &'a str,
+    remaining: usize,
+}
+
+impl<'a> PowerFailureEventLog<'a> {
+    /// Parse the log from an OBIS body, validating each of the failures.
+    pub fn parse(body: &'a str) -> Result<PowerFailureEventLog<'a>> {
+        let (count, body) = split_value(body)?;
+        let (reference, events) = split_value(body)?;
+        if reference != "(0-0:96.7.19)" {
+            return Err(Error::InvalidFormat);
+        }
+
+        let log = PowerFailureEventLog {
+            events,
+            remaining: count[1..count.len() - 1]
+                .parse()
+                .map_err(|_| Error::InvalidFormat)?,
+        };
+
+        if log.clone().any(|event| event.is_err()) {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(log)
+    }
+
+    fn parse_event(&mut self) -> Result<(TST, UFixedInteger)> {
+        let (time, events) = split_value(self.events)?;
+        let (duration, events) = split_value(events)?;
+        self.events = events;
+
+        // Some meters omit the unit.
+        let duration = &duration[1..duration.len() - 1];
+        let duration = duration.strip_suffix("*s").unwrap_or(duration);
+
+        Ok((
+            TST::parse(time)?,
+            UFixedInteger(duration.parse().map_err(|_| Error::InvalidFormat)?),
+        ))
+    }
+}
+
+impl<'a> core::iter::Iterator for PowerFailureEventLog<'a> {
+    type Item = Result<(TST, UFixedInteger)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        let event = self.parse_event();
+        if event.is_err() {
+            self.remaining = 0;
+        }
+        Some(event)
+    }
+}
+
+/// Serializes as a sequence of the failures, each as a tuple of the end time and the duration.
+impl Serialize for PowerFailureEventLog<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        serialize_entries(self.clone(), self.remaining, serializer)
+    }
+}
+
+/// History of the monthly peak demands of the last 13 months, as emitted by e-MUCS meters.
+///
+/// Yields the start of the month, when the peak occurred and the peak demand of each month.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MaximumDemandHistory<'a> {
+    months: &'a str,
+    remaining: usize,
+}
+
+impl<'a> MaximumDemandHistory<'a> {
+    /// Parse the history from an OBIS body, validating each of the months.
+    pub fn parse(body: &'a str) -> Result<MaximumDemandHistory<'a>> {
+        let (count, body) = split_value(body)?;
+        let (_, body) = split_value(body)?;
+        let (_, months) = split_value(body)?;
+
+        let history = MaximumDemandHistory {
+            months,
+            remaining: count[1..count.len() - 1]
+                .parse()
+                .map_err(|_| Error::InvalidFormat)?,
+        };
+
+        if history.clone().any(|month| month.is_err()) {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(history)
+    }
+
+    fn parse_month(&mut self) -> Result<(TST, TST, UFixedDouble)> {
+        let (start, months) = split_value(self.months)?;
+        let (time, months) = split_value(months)?;
+        let (demand, months) = split_value(months)?;
+        self.months = months;
+
+        Ok((
+            TST::parse(start)?,
+            TST::parse(time)?,
+            UFixedDouble::parse(demand, 5, 3)?,
+        ))
+    }
+}
+
+impl<'a> core::iter::Iterator for MaximumDemandHistory<'a> {
+    type Item = Result<(TST, TST, UFixedDouble)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        let month = self.parse_month();
+        if month.is_err() {
+            self.remaining = 0;
+        }
+        Some(month)
+    }
+}
+
+/// Serializes as a sequence of the months, each as a tuple of the start of the month, the time
+/// of the peak and the peak demand.
+impl Serialize for MaximumDemandHistory<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        serialize_entries(self.clone(), self.remaining, serializer)
+    }
+}
+
+/// Serialize the entries of a log as a sequence, failing on the first malformed entry.
+fn serialize_entries<T: Serialize, S: serde::Serializer>(
+    entries: impl Iterator<Item = Result<T>>,
+    len: usize,
+    serializer: S,
+) -> core::result::Result<S::Ok, S::Error> {
+    use serde::ser::{Error as _, SerializeSeq};
+
+    let mut seq = serializer.serialize_seq(Some(len))?;
+    for entry in entries {
+        seq.serialize_element(&entry.map_err(S::Error::custom)?)?;
+    }
+    seq.end()
+}
+
+/// The timestamps embedded in a single OBIS object, see `OBIS::into_timestamps`.
+pub(crate) enum Timestamps<'a> {
+    Single(Option<TST>),
+    Failures(PowerFailureEventLog<'a>),
+
+    /// The months of the history, along with the time of the peak of the current month.
+    Months(MaximumDemandHistory<'a>, Option<TST>),
+}
+
+impl Iterator for Timestamps<'_> {
+    type Item = Result<TST>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Timestamps::Single(tst) => tst.take().map(Ok),
+            Timestamps::Failures(log) => log.next().map(|e| e.map(|(end, _)| end)),
+            Timestamps::Months(history, peak) => match peak.take() {
+                Some(peak) => Some(Ok(peak)),
+                None => history.next().map(|m| {
+                    m.map(|(start, time, _)| {
+                        *peak = Some(time);
+                        start
+                    })
+                }),
+            },
+        }
+    }
+}
+
+/// The part of the installation an OBIS object pertains to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Scope {
+    /// The meter as a whole.
+    Common,
+    Line(Line),
+    Slave(Slave),
+}
+
 /// OBIS data objects like the current power usage.
 ///
 /// As per section 6.12 of the requirements specification.
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OBIS<'a> {
     Version(OctetString<'a>),
     DateTime(TST),
@@ -36,39 +267,156 @@ pub enum OBIS<'a> {
     MeterReadingTo(Tariff, UFixedDouble),
     MeterReadingBy(Tariff, UFixedDouble),
 
+    /// Reactive energy delivered to the client, in kvarh.
+    ReactiveEnergyTo(UFixedDouble),
+
+    /// Reactive energy received from the client, in kvarh.
+    ReactiveEnergyBy(UFixedDouble),
+
     /// Current Tariff applicable as reported by the meter.
     /// Note that the format of this string is not defined in the requirements.
     /// Check what your meter emits in practice.
     TariffIndicator(OctetString<'a>),
+
+    /// Peak demand of the current month, as 15 minute average, along with when it occurred.
+    ///
+    /// Emitted by e-MUCS meters, as it drives the Belgian capacity tariff.
+    MaximumDemandMonth(TST, UFixedDouble),
+
+    /// Peak demands of the previous months, see `MaximumDemandHistory`.
+    MaximumDemandYear(MaximumDemandHistory<'a>),
     PowerDelivered(UFixedDouble),
     PowerReceived(UFixedDouble),
     PowerFailures(UFixedInteger),
     LongPowerFailures(UFixedInteger),
-    PowerFailureEventLog, // TODO
-    TextMessage,          // TODO
-    TextMessageCode,      // TODO
+    PowerFailureEventLog(PowerFailureEventLog<'a>),
+
+    /// Breaker (switch) position, see `SwitchPosition` for the coding.
+    BreakerState(UFixedInteger),
+
+    /// Current above which the breaker opens, in A, as emitted by e-MUCS meters.
+    FuseSupervisionThreshold(UFixedInteger),
+
+    /// Text message sent by the grid operator, which is empty unless a message is pending.
+    TextMessage(OctetString<'a>),
+
+    /// Numeric code accompanying text messages, of up to eight digits as per DSMR 4.
+    TextMessageCode(OctetString<'a>),
     VoltageSags(Line, UFixedInteger),
     VoltageSwells(Line, UFixedInteger),
     InstantaneousVoltage(Line, UFixedDouble),
-    InstantaneousCurrent(Line, UFixedInteger),
+    /// Instantaneous current, in whole amperes as per the standard or fractional as reported
+    /// by some DSMR 5 firmware.
+    InstantaneousCurrent(Line, UFixedDouble),
     InstantaneousActivePowerPlus(Line, UFixedDouble),
     InstantaneousActivePowerNeg(Line, UFixedDouble),
+
+    /// Instantaneous reactive power delivered to the client, in kvar.
+    InstantaneousReactivePowerPlus(Line, UFixedDouble),
+
+    /// Instantaneous reactive power received from the client, in kvar.
+    InstantaneousReactivePowerNeg(Line, UFixedDouble),
     SlaveDeviceType(Slave, Option<UFixedInteger>),
     SlaveEquipmentIdentifier(Slave, OctetString<'a>),
-    SlaveMeterReading(Slave, TST, Option<UFixedDouble>),
+
+    /// Slave meter reading, with its unit to tell e.g. water and heat meters apart from gas meters.
+    ///
+    /// Holds no unit when the reading is empty, or when the meter omits it.
+    ///
+    /// Also yielded for the DSMR 3 profile form `0-n:24.3.0`, which is thus normalised to and
+    /// displayed as `0-n:24.2.1`.
+    SlaveMeterReading(Slave, TST, Option<UFixedDouble>, Option<Unit>),
+
+    /// Slave meter reading not corrected for temperature, as emitted by e-MUCS gas meters.
+    SlaveMeterReadingNonCorrected(Slave, TST, Option<UFixedDouble>, Option<Unit>),
+
+    /// Object with an unknown reference, holding the reference and the raw body.
+    ///
+    /// Only yielded when enabled by `ParseOptions::unknown_as_raw`.
+    Unknown(&'a str, &'a str),
 }
 
 impl<'a> OBIS<'a> {
+    /// The powerline this object pertains to, if any.
+    pub fn line(&self) -> Option<Line> {
+        match self {
+            OBIS::VoltageSags(l, _)
+            | OBIS::VoltageSwells(l, _)
+            | OBIS::InstantaneousVoltage(l, _)
+            | OBIS::InstantaneousCurrent(l, _)
+            | OBIS::InstantaneousActivePowerPlus(l, _)
+            | OBIS::InstantaneousActivePowerNeg(l, _)
+            | OBIS::InstantaneousReactivePowerPlus(l, _)
+            | OBIS::InstantaneousReactivePowerNeg(l, _) => Some(*l),
+            _ => None,
+        }
+    }
+
+    /// The slave meter this object pertains to, if any.
+    pub fn slave(&self) -> Option<Slave> {
+        match self {
+            OBIS::SlaveDeviceType(s, _)
+            | OBIS::SlaveEquipmentIdentifier(s, _)
+            | OBIS::SlaveMeterReading(s, _, _, _)
+            | OBIS::SlaveMeterReadingNonCorrected(s, _, _, _) => Some(*s),
+            _ => None,
+        }
+    }
+
+    /// The part of the installation this object pertains to.
+    pub fn scope(&self) -> Scope {
+        match (self.line(), self.slave()) {
+            (Some(l), _) => Scope::Line(l),
+            (_, Some(s)) => Scope::Slave(s),
+            _ => Scope::Common,
+        }
+    }
+
+    /// Decode a text message into `buf`, yielding the readable message, see `OctetString::decode_into`.
+    ///
+    /// Yields `None` for objects other than `TextMessage` and `TextMessageCode`.
+    pub fn decode_message_into<'b>(&self, buf: &'b mut [u8]) -> Option<Result<&'b str>> {
+        match self {
+            OBIS::TextMessage(m) | OBIS::TextMessageCode(m) => Some(m.decode_into(buf)),
+            _ => None,
+        }
+    }
+
+    /// The timestamps embedded in this object, such as capture times and those of logged events.
+    pub(crate) fn into_timestamps(self) -> Timestamps<'a> {
+        match self {
+            OBIS::DateTime(tst)
+            | OBIS::MaximumDemandMonth(tst, _)
+            | OBIS::SlaveMeterReading(_, tst, _, _)
+            | OBIS::SlaveMeterReadingNonCorrected(_, tst, _, _) => Timestamps::Single(Some(tst)),
+            OBIS::PowerFailureEventLog(log) => Timestamps::Failures(log),
+            OBIS::MaximumDemandYear(history) => Timestamps::Months(history, None),
+            _ => Timestamps::Single(None),
+        }
+    }
+
     pub fn parse(line: &'a str) -> Result<OBIS<'a>> {
-        let reference_end = line.find('(').ok_or(Error::InvalidFormat)?;
-        let (reference, body) = line.split_at(reference_end);
+        OBIS::parse_with(line, ParseOptions::strict())
+    }
+
+    /// Parse a line as tolerant as specified by the options.
+    ///
+    /// Unknown OBIS references are still yielded as `Error::UnknownObis`, as only
+    /// `Telegram::objects_with` regards `ParseOptions::unknown_as_raw`.
+    pub fn parse_with(line: &'a str, options: ParseOptions) -> Result<OBIS<'a>> {
+        let (reference, body) = split_reference(line)?;
+
+        #[cfg(feature = "lenient")]
+        let normalized = normalize_reference(reference);
+        #[cfg(feature = "lenient")]
+        let reference = normalized.as_deref().unwrap_or(reference);
 
         use Line::*;
         use Tariff::*;
 
         match reference {
-            "1-3:0.2.8" => Ok(OBIS::Version::<'a>(OctetString::parse(body, 2)?)),
-            "0-0:1.0.0" => Ok(OBIS::DateTime(TST::parse(body)?)),
+            "1-3:0.2.8" => Ok(OBIS::Version::<'a>(OctetString::parse_max(body, 6)?)),
+            "0-0:1.0.0" => Ok(OBIS::DateTime(parse_tst(body, options)?)),
             "0-0:96.1.1" => Ok(OBIS::EquipmentIdentifier::<'a>(OctetString::parse_max(
                 body, 96,
             )?)),
@@ -88,43 +436,55 @@ impl<'a> OBIS<'a> {
                 Tariff2,
                 UFixedDouble::parse(body, 9, 3)?,
             )),
+            "1-0:3.8.0" => Ok(OBIS::ReactiveEnergyTo(UFixedDouble::parse(body, 9, 3)?)),
+            "1-0:4.8.0" => Ok(OBIS::ReactiveEnergyBy(UFixedDouble::parse(body, 9, 3)?)),
             "0-0:96.14.0" => Ok(OBIS::TariffIndicator::<'a>(OctetString::parse(body, 4)?)),
+            "1-0:1.6.0" => {
+                let (time, demand) = split_value(body)?;
+                Ok(OBIS::MaximumDemandMonth(
+                    parse_tst(time, options)?,
+                    UFixedDouble::parse(demand, 5, 3)?,
+                ))
+            }
+            "0-0:98.1.0" => Ok(OBIS::MaximumDemandYear(MaximumDemandHistory::parse(body)?)),
             "1-0:1.7.0" => Ok(OBIS::PowerDelivered(UFixedDouble::parse(body, 5, 3)?)),
             "1-0:2.7.0" => Ok(OBIS::PowerReceived(UFixedDouble::parse(body, 5, 3)?)),
             "0-0:96.7.21" => Ok(OBIS::PowerFailures(UFixedInteger::parse(body, 5)?)),
             "0-0:96.7.9" => Ok(OBIS::LongPowerFailures(UFixedInteger::parse(body, 5)?)),
-            "1-0:99.97.0" => Ok(OBIS::PowerFailureEventLog), // TODO
+            "1-0:99.97.0" => Ok(OBIS::PowerFailureEventLog(PowerFailureEventLog::parse(
+                body,
+            )?)),
+            "0-0:96.3.10" => Ok(OBIS::BreakerState(UFixedInteger::parse(body, 1)?)),
+            "1-0:31.4.0" => {
+                if !options.lenient_units && body.get(4..) != Some("*A)") {
+                    return Err(Error::InvalidFormat);
+                }
+                Ok(OBIS::FuseSupervisionThreshold(UFixedInteger::parse(
+                    body, 3,
+                )?))
+            }
             "1-0:32.32.0" => Ok(OBIS::VoltageSags(Line1, UFixedInteger::parse(body, 5)?)),
             "1-0:52.32.0" => Ok(OBIS::VoltageSags(Line2, UFixedInteger::parse(body, 5)?)),
             "1-0:72.32.0" => Ok(OBIS::VoltageSags(Line3, UFixedInteger::parse(body, 5)?)),
             "1-0:32.36.0" => Ok(OBIS::VoltageSwells(Line1, UFixedInteger::parse(body, 5)?)),
             "1-0:52.36.0" => Ok(OBIS::VoltageSwells(Line2, UFixedInteger::parse(body, 5)?)),
             "1-0:72.36.0" => Ok(OBIS::VoltageSwells(Line3, UFixedInteger::parse(body, 5)?)),
-            "0-0:96.13.1" => Ok(OBIS::TextMessageCode), // TODO
-            "0-0:96.13.0" => Ok(OBIS::TextMessage),     // TODO
-            "1-0:31.7.0" => Ok(OBIS::InstantaneousCurrent(
-                Line1,
-                UFixedInteger::parse(body, 3)?,
-            )),
-            "1-0:51.7.0" => Ok(OBIS::InstantaneousCurrent(
-                Line2,
-                UFixedInteger::parse(body, 3)?,
-            )),
-            "1-0:71.7.0" => Ok(OBIS::InstantaneousCurrent(
-                Line3,
-                UFixedInteger::parse(body, 3)?,
-            )),
+            "0-0:96.13.1" => Ok(OBIS::TextMessageCode(OctetString::parse_max(body, 16)?)),
+            "0-0:96.13.0" => Ok(OBIS::TextMessage(OctetString::parse_max(body, 2048)?)),
+            "1-0:31.7.0" => Ok(OBIS::InstantaneousCurrent(Line1, parse_current(body)?)),
+            "1-0:51.7.0" => Ok(OBIS::InstantaneousCurrent(Line2, parse_current(body)?)),
+            "1-0:71.7.0" => Ok(OBIS::InstantaneousCurrent(Line3, parse_current(body)?)),
             "1-0:32.7.0" => Ok(OBIS::InstantaneousVoltage(
                 Line1,
-                UFixedDouble::parse(body, 4, 1)?,
+                UFixedDouble::parse_max(body, 5)?,
             )),
             "1-0:52.7.0" => Ok(OBIS::InstantaneousVoltage(
                 Line2,
-                UFixedDouble::parse(body, 4, 1)?,
+                UFixedDouble::parse_max(body, 5)?,
             )),
             "1-0:72.7.0" => Ok(OBIS::InstantaneousVoltage(
                 Line3,
-                UFixedDouble::parse(body, 4, 1)?,
+                UFixedDouble::parse_max(body, 5)?,
             )),
             "1-0:21.7.0" => Ok(OBIS::InstantaneousActivePowerPlus(
                 Line1,
@@ -150,10 +510,34 @@ impl<'a> OBIS<'a> {
                 Line3,
                 UFixedDouble::parse(body, 5, 3)?,
             )),
+            "1-0:23.7.0" => Ok(OBIS::InstantaneousReactivePowerPlus(
+                Line1,
+                UFixedDouble::parse(body, 5, 3)?,
+            )),
+            "1-0:43.7.0" => Ok(OBIS::InstantaneousReactivePowerPlus(
+                Line2,
+                UFixedDouble::parse(body, 5, 3)?,
+            )),
+            "1-0:63.7.0" => Ok(OBIS::InstantaneousReactivePowerPlus(
+                Line3,
+                UFixedDouble::parse(body, 5, 3)?,
+            )),
+            "1-0:24.7.0" => Ok(OBIS::InstantaneousReactivePowerNeg(
+                Line1,
+                UFixedDouble::parse(body, 5, 3)?,
+            )),
+            "1-0:44.7.0" => Ok(OBIS::InstantaneousReactivePowerNeg(
+                Line2,
+                UFixedDouble::parse(body, 5, 3)?,
+            )),
+            "1-0:64.7.0" => Ok(OBIS::InstantaneousReactivePowerNeg(
+                Line3,
+                UFixedDouble::parse(body, 5, 3)?,
+            )),
             _ => {
                 if reference.len() != 10 || reference.get(..2).ok_or(Error::InvalidFormat)? != "0-"
                 {
-                    return Err(Error::UnknownObis);
+                    return Err(Error::unknown_obis(reference));
                 }
 
                 let channel = reference[2..=2]
@@ -174,12 +558,12 @@ impl<'a> OBIS<'a> {
                     "24.1.0" => {
                         // handling the case where the Smart Meter sends an empty value
                         if body.contains("()") {
-                            return Ok(OBIS::SlaveDeviceType(channel, None));
+                            Ok(OBIS::SlaveDeviceType(channel, None))
                         } else {
-                            return Ok(OBIS::SlaveDeviceType(
+                            Ok(OBIS::SlaveDeviceType(
                                 channel,
                                 Some(UFixedInteger::parse(body, 3)?),
-                            ));
+                            ))
                         }
                     }
                     "96.1.0" => Ok(OBIS::SlaveEquipmentIdentifier::<'a>(
@@ -187,24 +571,691 @@ impl<'a> OBIS<'a> {
                         OctetString::parse_max(body, 96)?,
                     )),
                     "24.2.1" => {
-                        let end = body[1..].find('(').ok_or(Error::InvalidFormat)?;
-                        let (time, measurement) = body.split_at(end + 1);
+                        let (tst, mr, unit) = parse_slave_reading(body, options)?;
+                        Ok(OBIS::SlaveMeterReading(channel, tst, mr, unit))
+                    }
+                    "24.2.3" => {
+                        let (tst, mr, unit) = parse_slave_reading(body, options)?;
+                        Ok(OBIS::SlaveMeterReadingNonCorrected(channel, tst, mr, unit))
+                    }
+                    "24.3.0" => {
+                        let (tst, mr, unit) = parse_profile_reading(body, options)?;
+                        Ok(OBIS::SlaveMeterReading(channel, tst, mr, unit))
+                    }
+                    _ => Err(Error::unknown_obis(reference)),
+                }
+            }
+        }
+    }
+}
 
-                        let period = measurement.find('.').ok_or(Error::InvalidFormat)?;
+/// Displays the OBIS reference followed by the value and its unit, e.g. `1-0:1.8.1 => 576.239 kWh`.
+///
+/// Objects parsed from an alternative form display the canonical reference, see `SlaveMeterReading`.
+impl core::fmt::Display for OBIS<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // The OBIS reference group of the line, as each line is offset by 20.
+        let l = |base: u8, line: &Line| base + 20 * (*line as u8);
+        let t = |tariff: &Tariff| *tariff as u8 + 1;
+        let s = |slave: &Slave| *slave as u8 + 1;
 
-                        if body.contains("(00000000.0000)") {
-                            return Ok(OBIS::SlaveMeterReading(channel, TST::parse(time)?, None));
-                        } else {
-                            Ok(OBIS::SlaveMeterReading(
-                                channel,
-                                TST::parse(time)?,
-                                Some(UFixedDouble::parse(measurement, 8, 9 - period as u8)?),
-                            ))
-                        }
-                    }
-                    _ => Err(Error::UnknownObis),
+        match self {
+            OBIS::Version(v) => write!(f, "1-3:0.2.8 => {}", v),
+            OBIS::DateTime(tst) => write!(f, "0-0:1.0.0 => {}", tst),
+            OBIS::EquipmentIdentifier(ei) => write!(f, "0-0:96.1.1 => {}", ei),
+            OBIS::MeterReadingTo(tariff, mr) => {
+                write!(f, "1-0:1.8.{} => {} kWh", t(tariff), mr)
+            }
+            OBIS::MeterReadingBy(tariff, mr) => {
+                write!(f, "1-0:2.8.{} => {} kWh", t(tariff), mr)
+            }
+            OBIS::ReactiveEnergyTo(e) => write!(f, "1-0:3.8.0 => {} kvarh", e),
+            OBIS::ReactiveEnergyBy(e) => write!(f, "1-0:4.8.0 => {} kvarh", e),
+            OBIS::TariffIndicator(ti) => write!(f, "0-0:96.14.0 => {}", ti),
+            OBIS::MaximumDemandMonth(tst, p) => {
+                write!(f, "1-0:1.6.0 => {} kW at {}", p, tst)
+            }
+            OBIS::MaximumDemandYear(history) => {
+                write!(f, "0-0:98.1.0 => {} months", history.remaining)
+            }
+            OBIS::PowerDelivered(p) => write!(f, "1-0:1.7.0 => {} kW", p),
+            OBIS::PowerReceived(p) => write!(f, "1-0:2.7.0 => {} kW", p),
+            OBIS::PowerFailures(UFixedInteger(n)) => write!(f, "0-0:96.7.21 => {}", n),
+            OBIS::LongPowerFailures(UFixedInteger(n)) => write!(f, "0-0:96.7.9 => {}", n),
+            OBIS::PowerFailureEventLog(log) => {
+                write!(f, "1-0:99.97.0 => {} failures", log.remaining)
+            }
+            OBIS::BreakerState(UFixedInteger(n)) => write!(f, "0-0:96.3.10 => {}", n),
+            OBIS::FuseSupervisionThreshold(UFixedInteger(a)) => {
+                write!(f, "1-0:31.4.0 => {} A", a)
+            }
+            OBIS::TextMessage(m) => write!(f, "0-0:96.13.0 => {}", m),
+            OBIS::TextMessageCode(c) => write!(f, "0-0:96.13.1 => {}", c),
+            OBIS::VoltageSags(line, UFixedInteger(n)) => {
+                write!(f, "1-0:{}.32.0 => {}", l(32, line), n)
+            }
+            OBIS::VoltageSwells(line, UFixedInteger(n)) => {
+                write!(f, "1-0:{}.36.0 => {}", l(32, line), n)
+            }
+            OBIS::InstantaneousVoltage(line, v) => {
+                write!(f, "1-0:{}.7.0 => {} V", l(32, line), v)
+            }
+            OBIS::InstantaneousCurrent(line, a) => {
+                write!(f, "1-0:{}.7.0 => {} A", l(31, line), a)
+            }
+            OBIS::InstantaneousActivePowerPlus(line, p) => {
+                write!(f, "1-0:{}.7.0 => {} kW", l(21, line), p)
+            }
+            OBIS::InstantaneousActivePowerNeg(line, p) => {
+                write!(f, "1-0:{}.7.0 => {} kW", l(22, line), p)
+            }
+            OBIS::InstantaneousReactivePowerPlus(line, p) => {
+                write!(f, "1-0:{}.7.0 => {} kvar", l(23, line), p)
+            }
+            OBIS::InstantaneousReactivePowerNeg(line, p) => {
+                write!(f, "1-0:{}.7.0 => {} kvar", l(24, line), p)
+            }
+            OBIS::SlaveDeviceType(slave, dt) => {
+                write!(f, "0-{}:24.1.0", s(slave))?;
+                match dt {
+                    Some(UFixedInteger(dt)) => write!(f, " => {}", dt),
+                    None => Ok(()),
                 }
             }
+            OBIS::SlaveEquipmentIdentifier(slave, ei) => {
+                write!(f, "0-{}:96.1.0 => {}", s(slave), ei)
+            }
+            OBIS::SlaveMeterReading(slave, tst, mr, unit) => {
+                write!(f, "0-{}:24.2.1 => ", s(slave))?;
+                write_slave_reading(f, tst, mr, unit)
+            }
+            OBIS::SlaveMeterReadingNonCorrected(slave, tst, mr, unit) => {
+                write!(f, "0-{}:24.2.3 => ", s(slave))?;
+                write_slave_reading(f, tst, mr, unit)
+            }
+            OBIS::Unknown(reference, body) => write!(f, "{} => {}", reference, body),
+        }
+    }
+}
+
+fn write_slave_reading(
+    f: &mut core::fmt::Formatter<'_>,
+    tst: &TST,
+    mr: &Option<UFixedDouble>,
+    unit: &Option<Unit>,
+) -> core::fmt::Result {
+    if let Some(mr) = mr {
+        write!(f, "{} ", mr)?;
+        if let Some(unit) = unit {
+            write!(f, "{} ", unit)?;
         }
+        write!(f, "at ")?;
+    }
+    write!(f, "{}", tst)
+}
+
+/// Parse a timestamp, tolerating a missing daylight savings indicator if enabled by the options.
+fn parse_tst(body: &str, options: ParseOptions) -> Result<TST> {
+    if options.lenient_tst {
+        TST::parse_lenient(body)
+    } else {
+        TST::parse(body)
+    }
+}
+
+/// Split a line into its OBIS reference and body.
+pub(crate) fn split_reference(line: &str) -> Result<(&str, &str)> {
+    let reference_end = line.find('(').ok_or(Error::InvalidFormat)?;
+    Ok(line.split_at(reference_end))
+}
+
+/// Replace the colon some capture tools emit after the first group of a reference, e.g. `1:0:1.8.1`.
+///
+/// Only available with the `lenient` feature, yielding `None` if the reference needs no change.
+#[cfg(feature = "lenient")]
+fn normalize_reference(reference: &str) -> Option<heapless::String<16>> {
+    let (group, rest) = reference.split_once(':')?;
+    if !rest.contains(':') {
+        return None;
+    }
+
+    let mut normalized = heapless::String::new();
+    normalized.push_str(group).ok()?;
+    normalized.push('-').ok()?;
+    normalized.push_str(rest).ok()?;
+    Some(normalized)
+}
+
+/// Split the first value, including its parentheses, from the rest of a body.
+fn split_value(body: &str) -> Result<(&str, &str)> {
+    if !body.starts_with('(') {
+        return Err(Error::InvalidFormat);
+    }
+
+    let end = body.find(')').ok_or(Error::InvalidFormat)?;
+    Ok(body.split_at(end + 1))
+}
+
+/// Parse an instantaneous current, either in whole amperes as `(001*A)` or fractional as `(001.23*A)`.
+fn parse_current(body: &str) -> Result<UFixedDouble> {
+    match body.get(4..5) {
+        Some("." | ",") => UFixedDouble::parse_max(body, 6),
+        _ => Ok(UFixedDouble::from(UFixedInteger::parse(body, 3)?)),
+    }
+}
+
+/// Parse the capture time and value of a slave meter reading.
+///
+/// Yields no value when the meter reports an empty reading, and no unit when it is omitted.
+fn parse_slave_reading(
+    body: &str,
+    options: ParseOptions,
+) -> Result<(TST, Option<UFixedDouble>, Option<Unit>)> {
+    let end = body[1..].find('(').ok_or(Error::InvalidFormat)?;
+    let (time, measurement) = body.split_at(end + 1);
+
+    let period = measurement.find(['.', ',']).ok_or(Error::InvalidFormat)?;
+    let point = 9u8
+        .checked_sub(u8::try_from(period).map_err(|_| Error::InvalidFormat)?)
+        .ok_or(Error::InvalidFormat)?;
+
+    if body.contains("(00000000.0000)") {
+        Ok((parse_tst(time, options)?, None, None))
+    } else if measurement.contains('*') {
+        let mr = UFixedDouble::parse(measurement, 8, point)?;
+        let unit = match UFixedDouble::parse_with_unit(measurement, 8, point) {
+            Ok((_, unit)) => Some(unit),
+            Err(_) if options.lenient_units => None,
+            Err(e) => return Err(e),
+        };
+        Ok((parse_tst(time, options)?, Some(mr), unit))
+    } else {
+        Ok((
+            parse_tst(time, options)?,
+            Some(UFixedDouble::parse(measurement, 8, point)?),
+            None,
+        ))
+    }
+}
+
+/// Parse the capture time and value of a slave meter reading in the DSMR 3 profile format.
+///
+/// The profile holds the capture time, status, capture period, amount of values, OBIS reference
+/// and unit of the values, after which the value follows on the next line of the telegram.
+/// The capture time carries no daylight savings indicator, thus is taken to be winter time.
+fn parse_profile_reading(
+    body: &str,
+    options: ParseOptions,
+) -> Result<(TST, Option<UFixedDouble>, Option<Unit>)> {
+    let (time, rest) = split_value(body)?;
+    if time.len() != 14 {
+        return Err(Error::InvalidFormat);
+    }
+    let mut tst = heapless::String::<15>::new();
+    tst.push_str(&time[..13])
+        .map_err(|_| Error::InvalidFormat)?;
+    tst.push_str("W)").map_err(|_| Error::InvalidFormat)?;
+
+    let (_status, rest) = split_value(rest)?;
+    let (_period, rest) = split_value(rest)?;
+    let (count, rest) = split_value(rest)?;
+    if count != "(1)" {
+        return Err(Error::InvalidFormat);
+    }
+    let (_reference, rest) = split_value(rest)?;
+    let (unit, rest) = split_value(rest)?;
+    let unit = match Unit::parse(&unit[1..unit.len() - 1]) {
+        Ok(unit) => Some(unit),
+        Err(_) if options.lenient_units => None,
+        Err(e) => return Err(e),
+    };
+
+    let value = crate::line_ending::trim_start(rest);
+    if value == "()" {
+        return Ok((TST::parse(&tst)?, None, None));
+    }
+    if !value.ends_with(')') || crate::line_ending::contains(value) {
+        return Err(Error::InvalidFormat);
+    }
+
+    Ok((
+        TST::parse(&tst)?,
+        Some(UFixedDouble::parse_max(value, 9)?),
+        unit,
+    ))
+}
+
+/// Conversion of OBIS objects into a domain specific type.
+///
+/// Used in conjunction with `ObisIteratorExt::filter_map_into`:
+///
+/// ```
+/// use dsmr5::{FromObis, ObisIteratorExt, OBIS};
+///
+/// struct Power(f64);
+///
+/// impl<'a> FromObis<'a> for Power {
+///     fn from_obis(o: OBIS<'a>) -> Option<Self> {
+///         match o {
+///             OBIS::PowerDelivered(p) => Some(Power(f64::from(&p))),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let lines = ["0-0:96.7.21(00009)", "1-0:1.7.0(00.193*kW)"];
+/// let power: Vec<Power> = lines
+///     .into_iter()
+///     .map(OBIS::parse)
+///     .filter_map_into::<Power>()
+///     .collect::<dsmr5::Result<_>>()
+///     .unwrap();
+///
+/// assert_eq!(power.len(), 1);
+/// assert_eq!(power[0].0, 0.193);
+/// ```
+pub trait FromObis<'a>: Sized {
+    /// Convert the object, yielding `None` for objects that are not of interest.
+    fn from_obis(o: OBIS<'a>) -> Option<Self>;
+}
+
+/// Adaptors for iterators over OBIS objects, such as `Telegram::objects`.
+pub trait ObisIteratorExt<'a>: core::iter::Iterator<Item = Result<OBIS<'a>>> + Sized {
+    /// Convert the objects to `T`, skipping those that `T` is not interested in.
+    ///
+    /// Errors are passed through as is.
+    fn filter_map_into<T: FromObis<'a>>(self) -> impl core::iter::Iterator<Item = Result<T>> {
+        self.filter_map(|o| match o {
+            Ok(o) => T::from_obis(o).map(Ok),
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
+
+impl<'a, I: core::iter::Iterator<Item = Result<OBIS<'a>>>> ObisIteratorExt<'a> for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuse_supervision_threshold() {
+        let o = OBIS::parse("1-0:31.4.0(999*A)").unwrap();
+        assert!(matches!(
+            o,
+            OBIS::FuseSupervisionThreshold(UFixedInteger(999))
+        ));
+        assert_eq!(format!("{}", o), "1-0:31.4.0 => 999 A");
+
+        assert!(OBIS::parse("1-0:31.4.0(999*V)").is_err());
+        assert!(OBIS::parse("1-0:31.4.0(999)").is_err());
+    }
+
+    #[test]
+    fn lenient_options() {
+        let lenient = ParseOptions {
+            lenient_units: true,
+            lenient_tst: true,
+            ..ParseOptions::strict()
+        };
+
+        assert!(OBIS::parse("0-0:1.0.0(190320181403)").is_err());
+        let o = OBIS::parse_with("0-0:1.0.0(190320181403)", lenient).unwrap();
+        assert!(matches!(
+            o,
+            OBIS::DateTime(TST {
+                hour: 18,
+                dst: false,
+                ..
+            })
+        ));
+
+        assert!(OBIS::parse("1-0:31.4.0(999)").is_err());
+        assert!(matches!(
+            OBIS::parse_with("1-0:31.4.0(999)", lenient),
+            Ok(OBIS::FuseSupervisionThreshold(UFixedInteger(999)))
+        ));
+
+        let line = "0-1:24.2.1(190320181003W)(00012.345*dm3)";
+        assert!(OBIS::parse(line).is_err());
+        match OBIS::parse_with(line, lenient).unwrap() {
+            OBIS::SlaveMeterReading(Slave::Slave1, _, Some(mr), None) => {
+                assert_eq!(f64::from(&mr), 12.345)
+            }
+            _ => panic!(),
+        }
+
+        assert!(matches!(
+            OBIS::parse_with("1-0:0.0.0(0)", lenient),
+            Err(Error::UnknownObis(_))
+        ));
+    }
+
+    #[test]
+    fn colon_separator() {
+        let o = OBIS::parse("1:0:1.7.0(00.193*kW)");
+
+        #[cfg(feature = "lenient")]
+        assert!(matches!(o, Ok(OBIS::PowerDelivered(_))));
+        #[cfg(not(feature = "lenient"))]
+        assert!(matches!(o, Err(Error::UnknownObis(_))));
+
+        #[cfg(feature = "lenient")]
+        assert!(matches!(
+            OBIS::parse("0:1:24.1.0(003)"),
+            Ok(OBIS::SlaveDeviceType(Slave::Slave1, _))
+        ));
+    }
+
+    #[test]
+    fn breaker_state() {
+        let o = OBIS::parse("0-0:96.3.10(1)").unwrap();
+        assert!(matches!(o, OBIS::BreakerState(UFixedInteger(1))));
+
+        assert!(matches!(
+            SwitchPosition::try_from(1),
+            Ok(SwitchPosition::Connected)
+        ));
+        assert!(matches!(
+            SwitchPosition::try_from(2),
+            Ok(SwitchPosition::ReadyForReconnection)
+        ));
+        assert!(SwitchPosition::try_from(3).is_err());
+    }
+
+    #[test]
+    fn power_failure_event_log() {
+        let line =
+            "1-0:99.97.0(2)(0-0:96.7.19)(101208152415W)(0000000240*s)(101208151004W)(0000000301*s)";
+        let OBIS::PowerFailureEventLog(log) = OBIS::parse(line).unwrap() else {
+            panic!("unexpected object");
+        };
+
+        let events: std::vec::Vec<_> = log.clone().map(|e| e.unwrap()).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].0, TST::parse("(101208152415W)").unwrap());
+        assert_eq!(events[0].1 .0, 240);
+        assert_eq!(events[1].0, TST::parse("(101208151004W)").unwrap());
+        assert_eq!(events[1].1 .0, 301);
+        assert_eq!(
+            std::format!("{}", OBIS::PowerFailureEventLog(log)),
+            "1-0:99.97.0 => 2 failures"
+        );
+
+        // As emitted by Kaifa meters, without units.
+        let line = "1-0:99.97.0(2)(0-0:96.7.19)(000000000000S)(23)(000000000000W)(00000000)";
+        let OBIS::PowerFailureEventLog(log) = OBIS::parse(line).unwrap() else {
+            panic!("unexpected object");
+        };
+        let durations: std::vec::Vec<_> = log.map(|e| e.unwrap().1 .0).collect();
+        assert_eq!(durations, [23, 0]);
+
+        let empty = OBIS::parse("1-0:99.97.0(0)(0-0:96.7.19)").unwrap();
+        assert!(matches!(empty, OBIS::PowerFailureEventLog(log) if log.clone().count() == 0));
+
+        assert!(OBIS::parse("1-0:99.97.0(2)(0-0:96.7.19)(101208152415W)(0000000240*s)").is_err());
+        assert!(OBIS::parse("1-0:99.97.0(1)(0-0:96.7.18)(101208152415W)(0000000240*s)").is_err());
+    }
+
+    #[test]
+    fn text_message() {
+        let message = |line| match OBIS::parse(line).unwrap() {
+            OBIS::TextMessage(m) => {
+                let mut buf = [0u8; 64];
+                std::string::String::from(m.decode_into(&mut buf).unwrap())
+            }
+            o => panic!("unexpected {:?}", o),
+        };
+
+        assert_eq!(
+            message("0-0:96.13.0(4F6E646572686F7564206F702031322F3034)"),
+            "Onderhoud op 12/04"
+        );
+        assert_eq!(message("0-0:96.13.0()"), "");
+    }
+
+    #[test]
+    fn decode_message_into() {
+        let mut buf = [0u8; 64];
+        let o = OBIS::parse("0-0:96.13.0(53746F72696E67206F702031322F3034)").unwrap();
+        assert_eq!(
+            o.decode_message_into(&mut buf).unwrap().unwrap(),
+            "Storing op 12/04"
+        );
+
+        let o = OBIS::parse("0-0:96.13.1(3031323334353637)").unwrap();
+        assert_eq!(
+            o.decode_message_into(&mut buf).unwrap().unwrap(),
+            "01234567"
+        );
+
+        let mut small = [0u8; 4];
+        let o = OBIS::parse("0-0:96.13.0(53746F72696E67)").unwrap();
+        assert!(matches!(o.decode_message_into(&mut small), Some(Err(_))));
+
+        let o = OBIS::parse("1-0:1.7.0(00.193*kW)").unwrap();
+        assert!(o.decode_message_into(&mut buf).is_none());
+    }
+
+    #[test]
+    fn text_message_code() {
+        let code = OBIS::parse("0-0:96.13.1(3031323334353637)").unwrap();
+        let OBIS::TextMessageCode(code) = code else {
+            panic!("unexpected {:?}", code);
+        };
+        let octets: std::vec::Vec<u8> = code.as_octets().map(|b| b.unwrap()).collect();
+        assert_eq!(octets, b"01234567");
+
+        assert!(matches!(
+            OBIS::parse("0-0:96.13.1()"),
+            Ok(OBIS::TextMessageCode(_))
+        ));
+        assert!(OBIS::parse("0-0:96.13.1(303132333435363738)").is_err());
+    }
+
+    #[test]
+    fn maximum_demand_year() {
+        let file = std::fs::read("test/flu.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let Some(Ok(OBIS::MaximumDemandYear(history))) = telegram.find_object("0-0:98.1.0") else {
+            panic!("missing history");
+        };
+
+        let months: std::vec::Vec<_> = history.map(|m| m.unwrap()).collect();
+        assert_eq!(months.len(), 3);
+        assert_eq!(months[0].0, TST::parse("(200501000000S)").unwrap());
+        assert_eq!(months[0].1, TST::parse("(200423192538S)").unwrap());
+        assert_eq!(f64::from(&months[0].2), 3.695);
+        assert_eq!(months[2].1, TST::parse("(200210035421W)").unwrap());
+        assert_eq!(f64::from(&months[2].2), 4.318);
+
+        assert!(OBIS::parse(
+            "0-0:98.1.0(2)(1-0:1.6.0)(1-0:1.6.0)(200501000000S)(200423192538S)(03.695*kW)"
+        )
+        .is_err());
+        assert!(matches!(
+            OBIS::parse("0-0:98.1.0(0)(1-0:1.6.0)(1-0:1.6.0)"),
+            Ok(OBIS::MaximumDemandYear(_))
+        ));
+    }
+
+    #[test]
+    fn display() {
+        let display = |line| std::format!("{}", OBIS::parse(line).unwrap());
+
+        assert_eq!(
+            display("1-0:1.8.1(000576.239*kWh)"),
+            "1-0:1.8.1 => 576.239 kWh"
+        );
+        assert_eq!(display("1-0:52.7.0(236.1*V)"), "1-0:52.7.0 => 236.1 V");
+        assert_eq!(display("1-0:71.7.0(001*A)"), "1-0:71.7.0 => 1 A");
+        assert_eq!(display("1-0:42.7.0(00.193*kW)"), "1-0:42.7.0 => 0.193 kW");
+
+        // Values keep the decimals of the telegram.
+        assert_eq!(display("1-0:2.7.0(00.000*kW)"), "1-0:2.7.0 => 0.000 kW");
+        assert_eq!(
+            display("1-0:2.8.2(000000.010*kWh)"),
+            "1-0:2.8.2 => 0.010 kWh"
+        );
+        assert_eq!(
+            display("0-0:1.0.0(190320181403W)"),
+            "0-0:1.0.0 => 2019-03-20T18:14:03+01:00"
+        );
+        assert_eq!(
+            display("0-1:24.2.1(190320181003W)(00304.089*m3)"),
+            "0-1:24.2.1 => 304.089 m3 at 2019-03-20T18:10:03+01:00"
+        );
+
+        assert_eq!(
+            display("1-0:1.6.0(200509134558S)(02.589*kW)"),
+            "1-0:1.6.0 => 2.589 kW at 2020-05-09T13:45:58+02:00"
+        );
+
+        // The reference displayed should match the one parsed.
+        let file = std::fs::read_to_string("test/kaifa.txt").unwrap();
+        for line in file.lines().filter(|l| l.contains('(')) {
+            let reference = &line[..line.find('(').unwrap()];
+            assert!(display(line).starts_with(reference), "{}", line);
+        }
+    }
+
+    #[test]
+    fn active_power_lines() {
+        let power = |line| match OBIS::parse(line).unwrap() {
+            OBIS::InstantaneousActivePowerPlus(l, p) => (l, true, f64::from(&p)),
+            OBIS::InstantaneousActivePowerNeg(l, p) => (l, false, f64::from(&p)),
+            o => panic!("unexpected {:?}", o),
+        };
+
+        assert_eq!(power("1-0:21.7.0(01.111*kW)"), (Line::Line1, true, 1.111));
+        assert_eq!(power("1-0:41.7.0(02.222*kW)"), (Line::Line2, true, 2.222));
+        assert_eq!(power("1-0:61.7.0(03.333*kW)"), (Line::Line3, true, 3.333));
+        assert_eq!(power("1-0:22.7.0(04.444*kW)"), (Line::Line1, false, 4.444));
+        assert_eq!(power("1-0:42.7.0(05.555*kW)"), (Line::Line2, false, 5.555));
+        assert_eq!(power("1-0:62.7.0(06.666*kW)"), (Line::Line3, false, 6.666));
+    }
+
+    #[test]
+    fn slave_reading_point() {
+        match OBIS::parse("0-2:24.2.1(190320181003W)(00012.345*m3)").unwrap() {
+            OBIS::SlaveMeterReading(Slave::Slave2, _, Some(mr), Some(Unit::CubicMeter)) => {
+                assert_eq!(f64::from(&mr), 12.345)
+            }
+            o => panic!("unexpected {:?}", o),
+        }
+
+        // The decimal point lies beyond the digits of the reading.
+        assert!(matches!(
+            OBIS::parse("0-2:24.2.1(190320181003W)(0001234567.8*m3)"),
+            Err(Error::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn profile_reading() {
+        let o = OBIS::parse("0-1:24.3.0(121209190000)(00)(60)(1)(0-1:24.2.1)(m3)\r\n(00123.456)");
+        match o.unwrap() {
+            OBIS::SlaveMeterReading(Slave::Slave1, tst, Some(mr), Some(Unit::CubicMeter)) => {
+                assert_eq!(tst, TST::parse("(121209190000W)").unwrap());
+                assert_eq!(f64::from(&mr), 123.456);
+            }
+            o => panic!("unexpected {:?}", o),
+        }
+
+        let o = OBIS::parse("0-2:24.3.0(121209190000)(00)(60)(1)(0-2:24.2.1)(m3)\n()");
+        assert!(matches!(
+            o,
+            Ok(OBIS::SlaveMeterReading(Slave::Slave2, _, None, None))
+        ));
+
+        // The value line is missing.
+        assert!(OBIS::parse("0-1:24.3.0(121209190000)(00)(60)(1)(0-1:24.2.1)(m3)").is_err());
+
+        // The profile form is normalised to the reference of the DSMR 4 and 5 form.
+        let o = OBIS::parse("0-1:24.3.0(121209190000)(00)(60)(1)(0-1:24.2.1)(m3)\r\n(00123.456)");
+        assert_eq!(
+            std::format!("{}", o.unwrap()),
+            "0-1:24.2.1 => 123.456 m3 at 2012-12-09T19:00:00+01:00"
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn serialize() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let objects: std::vec::Vec<OBIS> = telegram.objects().map(|o| o.unwrap()).collect();
+
+        let json = serde_json::to_value(&objects).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 23);
+        assert_eq!(
+            json[8],
+            serde_json::json!({ "PowerDelivered": { "buffer": 193, "point": 3 } })
+        );
+        assert_eq!(
+            json[18],
+            serde_json::json!({ "InstantaneousActivePowerPlus": ["Line1", { "buffer": 193, "point": 3 }] })
+        );
+
+        let failures = objects
+            .iter()
+            .position(|o| matches!(o, OBIS::PowerFailureEventLog(_)))
+            .unwrap();
+        let log = &json[failures]["PowerFailureEventLog"];
+        assert_eq!(log.as_array().unwrap().len(), 6);
+        assert_eq!(log[0][1], serde_json::json!(3231));
+    }
+
+    #[test]
+    fn reactive_registers() {
+        match OBIS::parse("1-0:3.8.0(000012.345*kvarh)").unwrap() {
+            OBIS::ReactiveEnergyTo(e) => assert_eq!(f64::from(&e), 12.345),
+            o => panic!("unexpected {:?}", o),
+        }
+        match OBIS::parse("1-0:4.8.0(000001.002*kvarh)").unwrap() {
+            OBIS::ReactiveEnergyBy(e) => assert_eq!(f64::from(&e), 1.002),
+            o => panic!("unexpected {:?}", o),
+        }
+
+        let power = |line| match OBIS::parse(line).unwrap() {
+            OBIS::InstantaneousReactivePowerPlus(l, p) => (l, true, f64::from(&p)),
+            OBIS::InstantaneousReactivePowerNeg(l, p) => (l, false, f64::from(&p)),
+            o => panic!("unexpected {:?}", o),
+        };
+
+        assert_eq!(power("1-0:23.7.0(00.123*kvar)"), (Line::Line1, true, 0.123));
+        assert_eq!(power("1-0:43.7.0(00.456*kvar)"), (Line::Line2, true, 0.456));
+        assert_eq!(power("1-0:63.7.0(00.789*kvar)"), (Line::Line3, true, 0.789));
+        assert_eq!(power("1-0:24.7.0(01.000*kvar)"), (Line::Line1, false, 1.0));
+        assert_eq!(power("1-0:44.7.0(02.000*kvar)"), (Line::Line2, false, 2.0));
+        assert_eq!(power("1-0:64.7.0(03.000*kvar)"), (Line::Line3, false, 3.0));
+
+        let o = OBIS::parse("1-0:24.7.0(01.000*kvar)").unwrap();
+        assert_eq!(o.line(), Some(Line::Line1));
+        assert_eq!(format!("{}", o), "1-0:24.7.0 => 1.000 kvar");
+    }
+
+    #[test]
+    fn instantaneous_voltage() {
+        let voltage = |line| match OBIS::parse(line).unwrap() {
+            OBIS::InstantaneousVoltage(Line::Line2, v) => f64::from(&v),
+            o => panic!("unexpected {:?}", o),
+        };
+
+        assert_eq!(voltage("1-0:52.7.0(236.1*V)"), 236.1);
+        assert_eq!(voltage("1-0:52.7.0(236.12*V)"), 236.12);
+    }
+
+    #[test]
+    fn instantaneous_current() {
+        let current = |line| match OBIS::parse(line).unwrap() {
+            OBIS::InstantaneousCurrent(Line::Line1, a) => f64::from(&a),
+            o => panic!("unexpected {:?}", o),
+        };
+
+        assert_eq!(current("1-0:31.7.0(001*A)"), 1.0);
+        assert_eq!(current("1-0:31.7.0(001.23*A)"), 1.23);
+        assert_eq!(current("1-0:31.7.0(012,5*A)"), 12.5);
+        assert!(OBIS::parse("1-0:31.7.0(0a1*A)").is_err());
     }
 }
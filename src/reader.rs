@@ -1,24 +1,142 @@
+use core::convert::Infallible;
+
 use crate::Readout;
 
-/// A blocking Iterator that parses a bytestreaming Iterator to Readouts.
-pub struct Reader<T: core::iter::Iterator<Item = Result<u8, E>>, E> {
+/// A blocking Iterator that parses a bytestreaming Iterator to Readouts of up to `N` bytes.
+pub struct Reader<T: core::iter::Iterator<Item = Result<u8, E>>, E, const N: usize = 2048> {
     stream: T,
+    assembler: TelegramAssembler<N>,
 }
 
 impl<T: core::iter::Iterator<Item = Result<u8, E>>, E> Reader<T, E> {
     pub fn new(stream: T) -> Self {
-        Reader { stream }
+        Reader::sized(stream)
+    }
+}
+
+impl<T: core::iter::Iterator<Item = Result<u8, E>>, E, const N: usize> Reader<T, E, N> {
+    /// Create a reader yielding readouts of up to `N` bytes, see `Readout`.
+    pub fn sized(stream: T) -> Self {
+        Reader {
+            stream,
+            assembler: TelegramAssembler::new(),
+        }
+    }
+
+    /// Limit the amount of bytes skipped while looking for the start of a Readout.
+    ///
+    /// When exceeded, `next` yields `ReaderError::NoTelegramFound` instead of blocking
+    /// indefinitely on a line that does not carry telegrams.
+    pub fn with_max_skip_bytes(mut self, max_skip_bytes: usize) -> Self {
+        self.assembler.max_skip_bytes = Some(max_skip_bytes);
+        self
+    }
+
+    /// The bytes captured of the last readout that overflowed the buffer, see `TelegramAssembler::partial`.
+    pub fn partial(&self) -> &[u8] {
+        self.assembler.partial()
+    }
+
+    /// Convert the errors of the underlying stream, e.g. into the error type of an application.
+    pub fn map_err<E2>(
+        self,
+        mut f: impl FnMut(E) -> E2,
+    ) -> impl core::iter::Iterator<Item = Result<Readout<N>, ReaderError<E2>>> {
+        self.map(move |r| r.map_err(|e| e.map_io(&mut f)))
+    }
+}
+
+/// The bytes received by a serial peripheral, spinning while none are available.
+///
+/// Available with the `embedded-hal` feature, see `Reader::from_serial`.
+#[cfg(feature = "embedded-hal")]
+pub struct SerialBytes<R: embedded_hal::serial::Read<u8>>(R);
+
+#[cfg(feature = "embedded-hal")]
+impl<R: embedded_hal::serial::Read<u8>> Iterator for SerialBytes<R> {
+    type Item = Result<u8, R::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.0.read() {
+                Ok(b) => return Some(Ok(b)),
+                Err(nb::Error::WouldBlock) => core::hint::spin_loop(),
+                Err(nb::Error::Other(e)) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<R: embedded_hal::serial::Read<u8>> Reader<SerialBytes<R>, R::Error> {
+    /// Read from a serial peripheral such as the UART connected to the P1 port.
+    ///
+    /// Blocks by spinning until the peripheral receives bytes, whereas its
+    /// errors are yielded as `ReaderError::IOError`.
+    pub fn from_serial(serial: R) -> Self {
+        Reader::new(SerialBytes(serial))
     }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ReaderError<E> {
     IOError(E),
-    BufferOverFlow,
+
+    /// The readout did not fit the buffer, of which `partial_len` bytes were filled.
+    ///
+    /// The captured bytes remain available by `partial` until the next readout starts.
+    BufferOverFlow {
+        partial_len: usize,
+    },
+    NoTelegramFound,
 }
 
-impl<T: core::iter::Iterator<Item = Result<u8, E>>, E> Iterator for Reader<T, E> {
-    type Item = Result<Readout, ReaderError<E>>;
+impl<E: core::fmt::Display> core::fmt::Display for ReaderError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReaderError::IOError(e) => write!(f, "failed to read: {}", e),
+            ReaderError::BufferOverFlow { partial_len } => {
+                write!(f, "telegram overflows the buffer of {} bytes", partial_len)
+            }
+            ReaderError::NoTelegramFound => f.write_str("no telegram found"),
+        }
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for ReaderError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ReaderError::IOError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E> ReaderError<E> {
+    /// Convert the error of the underlying stream, leaving the other errors as is.
+    pub fn map_io<E2>(self, f: impl FnOnce(E) -> E2) -> ReaderError<E2> {
+        match self {
+            ReaderError::IOError(e) => ReaderError::IOError(f(e)),
+            ReaderError::BufferOverFlow { partial_len } => {
+                ReaderError::BufferOverFlow { partial_len }
+            }
+            ReaderError::NoTelegramFound => ReaderError::NoTelegramFound,
+        }
+    }
+}
+
+impl ReaderError<Infallible> {
+    /// Convert an error of the `TelegramAssembler` for use by a reader over a fallible stream.
+    fn widen<E>(self) -> ReaderError<E> {
+        self.map_io(|e| match e {})
+    }
+}
+
+impl<T: core::iter::Iterator<Item = Result<u8, E>>, E, const N: usize> Iterator
+    for Reader<T, E, N>
+{
+    type Item = Result<Readout<N>, ReaderError<E>>;
 
     /// Generates Readout by blocking on the underlying byte iterator until
     /// a full Readout was passed.
@@ -27,45 +145,214 @@ impl<T: core::iter::Iterator<Item = Result<u8, E>>, E> Iterator for Reader<T, E>
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             match self.stream.next()? {
-                Ok(b'/') => break,
-                Ok(_) => {}
-                Err(e) => return Some(Err(ReaderError::IOError(e))),
+                Ok(b) => {
+                    if let Some(result) = self.assembler.push(b) {
+                        return Some(result.map_err(ReaderError::widen));
+                    }
+                }
+                Err(e) => {
+                    self.assembler.reset();
+                    return Some(Err(ReaderError::IOError(e)));
+                }
             }
         }
+    }
+}
 
-        let mut buffer = [0u8; 2048];
-        buffer[0] = b'/';
+/// A Stream that parses a bytestreaming Stream to Readouts of up to `N` bytes.
+///
+/// The asynchronous counterpart of `Reader`, available with the `async` feature.
+#[cfg(feature = "async")]
+pub struct AsyncReader<S, E, const N: usize = 2048>
+where
+    S: futures_core::Stream<Item = Result<u8, E>> + Unpin,
+{
+    stream: S,
+    assembler: TelegramAssembler<N>,
+}
 
-        let mut i = 1;
-        let mut copy_byte = || match self.stream.next()? {
-            Ok(b) => {
-                if i >= buffer.len() {
-                    return Some(Err(ReaderError::BufferOverFlow));
-                }
+#[cfg(feature = "async")]
+impl<S, E> AsyncReader<S, E>
+where
+    S: futures_core::Stream<Item = Result<u8, E>> + Unpin,
+{
+    pub fn new(stream: S) -> Self {
+        AsyncReader::sized(stream)
+    }
+}
 
-                buffer[i] = b;
-                i += 1;
-                Some(Ok(b))
-            }
-            Err(e) => return Some(Err(ReaderError::IOError(e))),
-        };
+#[cfg(feature = "async")]
+impl<S, E, const N: usize> AsyncReader<S, E, N>
+where
+    S: futures_core::Stream<Item = Result<u8, E>> + Unpin,
+{
+    /// Create a reader yielding readouts of up to `N` bytes, see `Readout`.
+    pub fn sized(stream: S) -> Self {
+        AsyncReader {
+            stream,
+            assembler: TelegramAssembler::new(),
+        }
+    }
+
+    /// Limit the amount of bytes skipped while looking for the start of a Readout,
+    /// see `Reader::with_max_skip_bytes`.
+    pub fn with_max_skip_bytes(mut self, max_skip_bytes: usize) -> Self {
+        self.assembler.max_skip_bytes = Some(max_skip_bytes);
+        self
+    }
+
+    /// The bytes captured of the last readout that overflowed the buffer, see `TelegramAssembler::partial`.
+    pub fn partial(&self) -> &[u8] {
+        self.assembler.partial()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S, E, const N: usize> futures_core::Stream for AsyncReader<S, E, N>
+where
+    S: futures_core::Stream<Item = Result<u8, E>> + Unpin,
+{
+    type Item = Result<Readout<N>, ReaderError<E>>;
 
+    fn poll_next(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Self::Item>> {
+        use core::task::Poll;
+
+        let this = &mut *self;
         loop {
-            match copy_byte()? {
-                Ok(b'!') => {
-                    // Add CRC bytes
-                    for _ in 0..4 {
-                        if let Err(e) = copy_byte()? {
-                            return Some(Err(e));
-                        }
+            match core::pin::Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(b))) => {
+                    if let Some(result) = this.assembler.push(b) {
+                        return Poll::Ready(Some(result.map_err(ReaderError::widen)));
                     }
-                    return Some(Ok(Readout { buffer }));
                 }
-                Ok(_) => {}
-                Err(e) => return Some(Err(e)),
-            };
+                Poll::Ready(Some(Err(e))) => {
+                    this.assembler.reset();
+                    return Poll::Ready(Some(Err(ReaderError::IOError(e))));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Where the assembler is within a telegram.
+#[derive(Clone, Copy)]
+enum Phase {
+    /// Ignoring all bytes until the start of a telegram, counting them.
+    Seeking(usize),
+    /// Copying the telegram until its terminating '!'.
+    Data,
+    /// Copying the remaining amount of CRC bytes.
+    Crc(u8),
+}
+
+/// Assembles Readouts of up to `N` bytes from bytes pushed one at a time.
+///
+/// Unlike `Reader` this does not own the byte stream, e.g. to be fed from an interrupt handler.
+/// Will ignore all bytes until the first Readout is spotted.
+pub struct TelegramAssembler<const N: usize = 2048> {
+    buffer: [u8; N],
+    len: usize,
+    partial_len: usize,
+    phase: Phase,
+    max_skip_bytes: Option<usize>,
+}
+
+impl<const N: usize> TelegramAssembler<N> {
+    pub fn new() -> Self {
+        TelegramAssembler {
+            buffer: [0u8; N],
+            len: 0,
+            partial_len: 0,
+            phase: Phase::Seeking(0),
+            max_skip_bytes: None,
+        }
+    }
+
+    /// Limit the amount of bytes skipped while looking for the start of a Readout,
+    /// see `Reader::with_max_skip_bytes`.
+    pub fn with_max_skip_bytes(mut self, max_skip_bytes: usize) -> Self {
+        self.max_skip_bytes = Some(max_skip_bytes);
+        self
+    }
+
+    /// Drop the partially assembled readout, if any, and seek the start of the next one.
+    pub fn reset(&mut self) {
+        self.buffer[..self.len.max(self.partial_len)].fill(0);
+        self.len = 0;
+        self.partial_len = 0;
+        self.phase = Phase::Seeking(0);
+    }
+
+    /// The bytes captured of the last readout that overflowed the buffer.
+    ///
+    /// Empty once the next readout starts, or if no readout overflowed.
+    pub fn partial(&self) -> &[u8] {
+        &self.buffer[..self.partial_len]
+    }
+
+    /// Feed a single byte, yielding a result once a readout is complete or had to be dropped.
+    pub fn push(&mut self, b: u8) -> Option<Result<Readout<N>, ReaderError<Infallible>>> {
+        match self.phase {
+            Phase::Seeking(_) if b == b'/' => {
+                self.buffer[..self.partial_len].fill(0);
+                self.partial_len = 0;
+                self.phase = Phase::Data;
+                self.copy_byte(b)
+            }
+            Phase::Seeking(skipped) => {
+                let skipped = skipped + 1;
+                if self.max_skip_bytes.is_some_and(|max| skipped > max) {
+                    self.phase = Phase::Seeking(0);
+                    return Some(Err(ReaderError::NoTelegramFound));
+                }
+
+                self.phase = Phase::Seeking(skipped);
+                None
+            }
+            Phase::Data => {
+                if b == b'!' {
+                    self.phase = Phase::Crc(4);
+                }
+                self.copy_byte(b)
+            }
+            Phase::Crc(remaining) => {
+                self.phase = Phase::Crc(remaining - 1);
+                self.copy_byte(b)
+            }
         }
     }
+
+    fn copy_byte(&mut self, b: u8) -> Option<Result<Readout<N>, ReaderError<Infallible>>> {
+        if self.len >= N {
+            // Keep the captured bytes around for inspection, until the next readout starts.
+            let partial_len = core::mem::take(&mut self.len);
+            self.partial_len = partial_len;
+            self.phase = Phase::Seeking(0);
+            return Some(Err(ReaderError::BufferOverFlow { partial_len }));
+        }
+
+        self.buffer[self.len] = b;
+        self.len += 1;
+
+        if let Phase::Crc(0) = self.phase {
+            let len = core::mem::take(&mut self.len);
+            self.phase = Phase::Seeking(0);
+            let buffer = core::mem::replace(&mut self.buffer, [0u8; N]);
+            return Some(Ok(Readout { buffer, len }));
+        }
+        None
+    }
+}
+
+impl<const N: usize> Default for TelegramAssembler<N> {
+    fn default() -> Self {
+        TelegramAssembler::new()
+    }
 }
 
 #[cfg(test)]
@@ -74,9 +361,9 @@ mod tests {
     fn reader() {
         use std::io::Read;
 
-        let f1 = std::fs::File::open("test/isk.txt").unwrap().bytes();
-        let f2 = std::fs::File::open("test/isk.txt").unwrap().bytes();
-        let f3 = std::fs::File::open("test/isk.txt").unwrap().bytes();
+        let f1 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
+        let f2 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
+        let f3 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
 
         let mut bytes = f1.chain(f2).chain(f3);
 
@@ -99,21 +386,208 @@ mod tests {
     fn recover_from_overflow() {
         use std::io::Read;
 
-        let f1 = std::fs::File::open("test/isk.txt").unwrap().bytes();
-        let f2 = std::fs::File::open("test/overflow.txt").unwrap().bytes();
-        let f3 = std::fs::File::open("test/isk.txt").unwrap().bytes();
+        let f1 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
+        let f2 = std::io::BufReader::new(std::fs::File::open("test/overflow.txt").unwrap()).bytes();
+        let f3 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
         let bytes = f1.chain(f2).chain(f3);
 
         let mut reader = crate::Reader::new(bytes);
 
         let t1 = reader.next();
         let t2 = reader.next();
+        assert!(matches!(t1, Some(Ok(_))));
+        assert!(matches!(
+            t2,
+            Some(Err(crate::ReaderError::BufferOverFlow {
+                partial_len: 2048
+            }))
+        ));
+        assert_eq!(reader.partial().len(), 2048);
+        assert_eq!(reader.partial()[0], b'/');
+
         let t3 = reader.next();
         let t4 = reader.next();
+        assert!(matches!(t3, Some(Ok(_))));
+        assert!(reader.partial().is_empty());
+        assert!(t4.is_none());
+    }
 
-        assert!(matches!(t1, Some(Ok(_))));
-        assert!(matches!(t2, Some(Err(crate::ReaderError::BufferOverFlow))));
+    #[test]
+    fn map_err() {
+        #[derive(Debug)]
+        enum AppError {
+            Serial(std::io::ErrorKind),
+        }
+
+        let bytes = std::fs::read("test/isk.txt")
+            .unwrap()
+            .into_iter()
+            .map(Ok)
+            .chain([Err(std::io::Error::other("disconnected"))]);
+        let mut reader = crate::Reader::new(bytes).map_err(|e| AppError::Serial(e.kind()));
+
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        assert!(matches!(
+            reader.next(),
+            Some(Err(crate::ReaderError::IOError(AppError::Serial(
+                std::io::ErrorKind::Other
+            ))))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn sized() {
+        use std::io::Read;
+
+        let f1 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
+        let f2 = std::io::BufReader::new(std::fs::File::open("test/short.txt").unwrap()).bytes();
+        let mut reader = crate::Reader::<_, _, 512>::sized(f1.chain(f2));
+
+        assert!(matches!(
+            reader.next(),
+            Some(Err(crate::ReaderError::BufferOverFlow { .. }))
+        ));
+        let readout: crate::Readout<512> = reader.next().unwrap().unwrap();
+        assert_eq!(readout.to_telegram().unwrap().objects().count(), 8);
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_reader() {
+        use futures::StreamExt;
+
+        let mut bytes = std::fs::read("test/isk.txt").unwrap();
+        bytes.extend(std::fs::read("test/isk.txt").unwrap());
+        let stream = futures::stream::iter(bytes[100..].iter().map(|&b| Ok::<_, ()>(b)));
+
+        let mut reader = crate::AsyncReader::new(stream);
+        futures::executor::block_on(async {
+            let readout = reader.next().await.unwrap().unwrap();
+            assert_eq!(readout.to_telegram().unwrap().prefix, "ISK");
+            assert!(reader.next().await.is_none());
+        });
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    #[test]
+    fn from_serial() {
+        /// Receives a byte every other read, with a framing error halfway.
+        struct Serial {
+            bytes: std::vec::IntoIter<u8>,
+            ready: bool,
+        }
+
+        impl embedded_hal::serial::Read<u8> for Serial {
+            type Error = &'static str;
+
+            fn read(&mut self) -> nb::Result<u8, Self::Error> {
+                self.ready = !self.ready;
+                if !self.ready {
+                    return Err(nb::Error::WouldBlock);
+                }
+
+                match self.bytes.next() {
+                    Some(b'#') => Err(nb::Error::Other("framing")),
+                    Some(b) => Ok(b),
+                    None => Err(nb::Error::WouldBlock),
+                }
+            }
+        }
+
+        let mut bytes = std::fs::read("test/isk.txt").unwrap();
+        bytes.push(b'#');
+        bytes.extend(std::fs::read("test/isk.txt").unwrap());
+
+        let serial = Serial {
+            bytes: bytes.into_iter(),
+            ready: false,
+        };
+        let mut reader = crate::Reader::from_serial(serial);
+
+        assert!(reader.next().unwrap().unwrap().to_telegram().is_ok());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(crate::ReaderError::IOError("framing")))
+        ));
+        assert!(reader.next().unwrap().unwrap().to_telegram().is_ok());
+    }
+
+    #[test]
+    fn readout_len() {
+        use std::io::Read;
+
+        let file = std::fs::read("test/short.txt").unwrap();
+        let junk = std::iter::repeat_n(b'x', 10).map(Ok);
+        let f1 = std::io::BufReader::new(file.as_slice()).bytes();
+        let mut reader = crate::Reader::new(junk.chain(f1));
+
+        // The readout spans the fixture, up to the line ending trailing the checksum.
+        let readout = reader.next().unwrap().unwrap();
+        assert_eq!(readout.len, file.len() - 2);
+        assert_eq!(readout.as_telegram_bytes(), &file[..file.len() - 2]);
+    }
+
+    #[test]
+    fn telegram_assembler() {
+        let mut bytes = b"junk".to_vec();
+        bytes.extend(std::fs::read("test/isk.txt").unwrap());
+        bytes.extend(std::fs::read("test/overflow.txt").unwrap());
+        bytes.extend(std::fs::read("test/kaifa.txt").unwrap());
+
+        let mut assembler = crate::TelegramAssembler::<2048>::new();
+        let results: std::vec::Vec<_> = bytes.iter().filter_map(|&b| assembler.push(b)).collect();
+        assert_eq!(results.len(), 3);
+
+        let t1 = results[0].as_ref().unwrap().to_telegram().unwrap();
+        assert_eq!(t1.prefix, "ISK");
+        assert!(matches!(
+            results[1],
+            Err(crate::ReaderError::BufferOverFlow { .. })
+        ));
+        let t3 = results[2].as_ref().unwrap().to_telegram().unwrap();
+        assert_eq!(t3.prefix, "KFM");
+    }
+
+    #[test]
+    fn error_display() {
+        use core::error::Error;
+
+        let io = std::io::Error::other("disconnected");
+        let e = crate::ReaderError::IOError(io);
+        assert_eq!(format!("{}", e), "failed to read: disconnected");
+        assert!(e.source().is_some());
+
+        let e = crate::ReaderError::<std::io::Error>::BufferOverFlow { partial_len: 2048 };
+        assert_eq!(
+            format!("{}", e),
+            "telegram overflows the buffer of 2048 bytes"
+        );
+        assert!(e.source().is_none());
+
+        let e = crate::ReaderError::<std::io::Error>::NoTelegramFound;
+        assert_eq!(format!("{}", e), "no telegram found");
+    }
+
+    #[test]
+    fn max_skip_bytes() {
+        use std::io::Read;
+
+        let junk = std::iter::repeat_n(b'x', 5000).map(Ok);
+        let f1 = std::io::BufReader::new(std::fs::File::open("test/isk.txt").unwrap()).bytes();
+        let bytes = junk.chain(f1);
+
+        let mut reader = crate::Reader::new(bytes).with_max_skip_bytes(2048);
+
+        let t1 = reader.next();
+        let t2 = reader.next();
+        let t3 = reader.next();
+        let t4 = reader.next();
+
+        assert!(matches!(t1, Some(Err(crate::ReaderError::NoTelegramFound))));
+        assert!(matches!(t2, Some(Err(crate::ReaderError::NoTelegramFound))));
         assert!(matches!(t3, Some(Ok(_))));
-        assert!(matches!(t4, None));
+        assert!(t4.is_none());
     }
 }
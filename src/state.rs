@@ -9,115 +9,740 @@ use serde::{Deserialize, Serialize};
 use crate::{obis::*, types::*};
 
 /// A reading from a power meter, per Tariff.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MeterReading {
     pub to: Option<f64>,
     pub by: Option<f64>,
 }
 
+/// A meter reading labelled with the tariff it was registered under, see `State::readings`.
+///
+/// Unlike a bare `MeterReading` it remains self-describing when passed around or serialized alone.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TariffReading<'a> {
+    pub tariff: Tariff,
+    pub reading: &'a MeterReading,
+}
+
 /// One of three possible lines in the meter.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Line {
     pub voltage_sags: Option<u64>,
     pub voltage_swells: Option<u64>,
     pub voltage: Option<f64>,
-    pub current: Option<u64>,
+    pub current: Option<f64>,
     pub active_power_plus: Option<f64>,
     pub active_power_neg: Option<f64>,
 }
 
+impl Line {
+    /// Whether the meter reported any value for this line.
+    fn is_present(&self) -> bool {
+        self.voltage_sags.is_some()
+            || self.voltage_swells.is_some()
+            || self.voltage.is_some()
+            || self.current.is_some()
+            || self.active_power_plus.is_some()
+            || self.active_power_neg.is_some()
+    }
+}
+
+/// Kind of slave device, as per the M-Bus device type codes of EN 13757-3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceType {
+    Electricity,
+    Gas,
+    Heat,
+    Water,
+    Unknown(u8),
+}
+
+impl DeviceType {
+    /// The M-Bus device type code.
+    pub fn code(&self) -> u8 {
+        match self {
+            DeviceType::Electricity => 2,
+            DeviceType::Gas => 3,
+            DeviceType::Heat => 4,
+            DeviceType::Water => 7,
+            DeviceType::Unknown(code) => *code,
+        }
+    }
+}
+
+impl From<u8> for DeviceType {
+    fn from(code: u8) -> Self {
+        match code {
+            2 => DeviceType::Electricity,
+            3 => DeviceType::Gas,
+            4 => DeviceType::Heat,
+            7 => DeviceType::Water,
+            code => DeviceType::Unknown(code),
+        }
+    }
+}
+
 /// One of 4 possible slaves to the meter.
 ///
 /// Such as a gas meter, water meter or heat supply.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Slave {
     pub device_type: Option<u64>,
     pub meter_reading: Option<(TST, f64)>,
+
+    /// Meter reading not corrected for temperature, as reported by some gas meters.
+    pub meter_reading_noncorrected: Option<(TST, f64)>,
+
+    /// Unit of the meter readings, e.g. m3 for gas and water or GJ for heat, if reported.
+    pub unit: Option<Unit>,
+}
+
+impl Slave {
+    /// Interpret the device type code reported by the slave.
+    ///
+    /// Yields `None` if no device type is known, or the code exceeds a single byte.
+    pub fn device_type_kind(&self) -> Option<DeviceType> {
+        u8::try_from(self.device_type?).ok().map(DeviceType::from)
+    }
+
+    /// Seconds elapsed between the capture of the meter reading and a reference time, such as
+    /// a wall clock.
+    ///
+    /// Slaves such as gas meters only report every few minutes, so the reading may be stale.
+    /// Yields `None` if no reading is known or either timestamp is not a valid date.
+    pub fn reading_age_against(&self, now: &TST) -> Option<i64> {
+        let (captured, _) = self.meter_reading.as_ref()?;
+        now.difference_seconds(captured)
+    }
+
+    /// Seconds elapsed between the capture of the meter reading and the time of the telegram.
+    ///
+    /// Same as `reading_age_against`, for the common case of the `datetime` of the telegram
+    /// holding the reading as reference.
+    pub fn reading_age_seconds(&self, telegram_time: &TST) -> Option<i64> {
+        self.reading_age_against(telegram_time)
+    }
+}
+
+/// Voltage sags and swells of all lines, as gathered by `State::voltage_event_summary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoltageEventSummary {
+    pub sags: [Option<u64>; 3],
+    pub swells: [Option<u64>; 3],
+
+    /// Number of lines reported by the meter, i.e. 1 for single-phase and 3 for three-phase.
+    pub phase_count: usize,
+}
+
+/// Lowest line voltage considered plausible, in V.
+pub const MIN_PLAUSIBLE_VOLTAGE: f64 = 180.0;
+
+/// Highest line voltage considered plausible, in V.
+pub const MAX_PLAUSIBLE_VOLTAGE: f64 = 260.0;
+
+/// Highest line current considered plausible, in A.
+pub const MAX_PLAUSIBLE_CURRENT: f64 = 100.0;
+
+/// Highest power considered plausible, in kW, corresponding to three lines of 80A at 230V.
+pub const MAX_PLAUSIBLE_POWER: f64 = 55.2;
+
+/// Factor by which a line current may exceed the fuse supervision threshold, as the breaker
+/// only opens once the threshold has been exceeded for a while.
+pub const FUSE_SUPERVISION_MARGIN: f64 = 1.5;
+
+/// Decrease of a meter reading between two states considered plausible, in kWh, as some meters
+/// round the reading to fewer decimals from time to time.
+pub const MAX_PLAUSIBLE_READING_DECREASE: f64 = 0.001;
+
+/// A physically implausible value found by `State::sanity_check`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SanityError {
+    /// The datetime does not represent a valid date.
+    InvalidDateTime,
+
+    /// Power is both delivered and received at the same time.
+    SimultaneousPower,
+    PowerDelivered(f64),
+    PowerReceived(f64),
+    Voltage(crate::obis::Line, f64),
+    Current(crate::obis::Line, f64),
+    ActivePower(crate::obis::Line, f64),
+
+    /// The line current exceeds the fuse supervision threshold, yet the breaker did not open.
+    CurrentAboveThreshold(crate::obis::Line, f64),
+
+    /// A meter reading of the tariff is negative.
+    NegativeMeterReading(Tariff, f64),
+
+    /// A meter reading of the tariff went backwards, as found by `State::sanity_check_against`.
+    MeterReadingDecreased(Tariff, f64),
 }
 
 /// The metering state surmised for a single Telegram.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct State {
     pub datetime: Option<TST>,
     pub meterreadings: [MeterReading; 2],
     pub tariff_indicator: Option<[u8; 2]>,
+    /// Peak demand of the current month in kW, along with when it occurred, see
+    /// `OBIS::MaximumDemandMonth`.
+    pub maximum_demand_month: Option<(TST, f64)>,
+
+    /// Power delivered to the client by the grid (i.e. imported), in kW.
     pub power_delivered: Option<f64>,
+
+    /// Power received from the client by the grid (i.e. exported), in kW.
     pub power_received: Option<f64>,
     pub power_failures: Option<u64>,
     pub long_power_failures: Option<u64>,
+
+    /// Breaker position on remotely controllable meters, see `SwitchPosition`.
+    pub switch_position: Option<u64>,
+
+    /// Current above which the breaker opens, in A, see `fuse_supervision_amps`.
+    pub fuse_supervision_threshold: Option<u64>,
     pub lines: [Line; 3],
     pub slaves: [Slave; 4],
 }
 
-impl<'a> core::convert::TryFrom<&crate::Telegram<'a>> for State {
-    type Error = crate::Error;
+impl State {
+    /// Iterate over the meter readings, each labelled with the tariff it was registered under.
+    pub fn readings(&self) -> impl core::iter::Iterator<Item = TariffReading<'_>> {
+        [Tariff::Tariff1, Tariff::Tariff2]
+            .into_iter()
+            .zip(self.meterreadings.iter())
+            .map(|(tariff, reading)| TariffReading { tariff, reading })
+    }
 
-    fn try_from(t: &crate::Telegram<'a>) -> Result<Self, Self::Error> {
-        t.objects().try_fold(State::default(), |mut state, o| {
-            match o? {
-                OBIS::DateTime(tst) => {
-                    state.datetime = Some(tst);
-                }
-                OBIS::MeterReadingTo(t, mr) => {
-                    state.meterreadings[t as usize].to = Some(f64::from(&mr));
-                }
-                OBIS::MeterReadingBy(t, mr) => {
-                    state.meterreadings[t as usize].by = Some(f64::from(&mr));
-                }
-                OBIS::TariffIndicator(ti) => {
-                    let mut buf = [0u8; 2];
-                    let mut octets = ti.as_octets();
-                    buf[0] = octets.next().unwrap_or(Err(crate::Error::InvalidFormat))?;
-                    buf[1] = octets.next().unwrap_or(Err(crate::Error::InvalidFormat))?;
+    /// Check the state for physically implausible values, yielding the first violation.
+    ///
+    /// Such values hint at a corrupted telegram that passed the CRC check, or a misbehaving meter.
+    /// The thresholds used are the `MIN_PLAUSIBLE_*` and `MAX_PLAUSIBLE_*` constants, along with
+    /// the fuse supervision threshold reported by the meter and `FUSE_SUPERVISION_MARGIN`.
+    pub fn sanity_check(&self) -> Result<(), SanityError> {
+        if let Some(datetime) = &self.datetime {
+            datetime.to_unix().ok_or(SanityError::InvalidDateTime)?;
+        }
 
-                    state.tariff_indicator = Some(buf);
-                }
-                OBIS::PowerDelivered(p) => {
-                    state.power_delivered = Some(f64::from(&p));
-                }
-                OBIS::PowerReceived(p) => {
-                    state.power_received = Some(f64::from(&p));
+        for TariffReading { tariff, reading } in self.readings() {
+            for mr in [reading.to, reading.by].into_iter().flatten() {
+                if mr < 0.0 {
+                    return Err(SanityError::NegativeMeterReading(tariff, mr));
                 }
-                OBIS::PowerFailures(UFixedInteger(pf)) => {
-                    state.power_failures = Some(pf);
-                }
-                OBIS::LongPowerFailures(UFixedInteger(lpf)) => {
-                    state.long_power_failures = Some(lpf);
+            }
+        }
+
+        let delivered = self.power_delivered.unwrap_or(0.0);
+        let received = self.power_received.unwrap_or(0.0);
+        if delivered > 0.0 && received > 0.0 {
+            return Err(SanityError::SimultaneousPower);
+        }
+        if delivered > MAX_PLAUSIBLE_POWER {
+            return Err(SanityError::PowerDelivered(delivered));
+        }
+        if received > MAX_PLAUSIBLE_POWER {
+            return Err(SanityError::PowerReceived(received));
+        }
+
+        for (l, line) in self.present_lines() {
+            if let Some(v) = line.voltage {
+                if !(MIN_PLAUSIBLE_VOLTAGE..=MAX_PLAUSIBLE_VOLTAGE).contains(&v) {
+                    return Err(SanityError::Voltage(l, v));
                 }
-                OBIS::VoltageSags(l, UFixedInteger(n)) => {
-                    state.lines[l as usize].voltage_sags = Some(n);
+            }
+            if let Some(a) = line.current {
+                if a > MAX_PLAUSIBLE_CURRENT {
+                    return Err(SanityError::Current(l, a));
                 }
-                OBIS::VoltageSwells(l, UFixedInteger(n)) => {
-                    state.lines[l as usize].voltage_swells = Some(n);
+                if let Some(threshold) = self.fuse_supervision_threshold {
+                    if a > threshold as f64 * FUSE_SUPERVISION_MARGIN {
+                        return Err(SanityError::CurrentAboveThreshold(l, a));
+                    }
                 }
-                OBIS::InstantaneousVoltage(l, v) => {
-                    state.lines[l as usize].voltage = Some(f64::from(&v));
+            }
+            for p in [line.active_power_plus, line.active_power_neg]
+                .into_iter()
+                .flatten()
+            {
+                if p > MAX_PLAUSIBLE_POWER {
+                    return Err(SanityError::ActivePower(l, p));
                 }
-                OBIS::InstantaneousCurrent(l, UFixedInteger(a)) => {
-                    state.lines[l as usize].current = Some(a);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the state like `sanity_check`, and additionally against a previous state of the meter.
+    ///
+    /// Meter readings only ever increase, so a reading lower than before by more than
+    /// `MAX_PLAUSIBLE_READING_DECREASE` hints at a misframed telegram.
+    pub fn sanity_check_against(&self, previous: &State) -> Result<(), SanityError> {
+        self.sanity_check()?;
+
+        for (current, previous) in self.readings().zip(previous.readings()) {
+            for (mr, prev) in [
+                (current.reading.to, previous.reading.to),
+                (current.reading.by, previous.reading.by),
+            ] {
+                if let (Some(mr), Some(prev)) = (mr, prev) {
+                    if prev - mr > MAX_PLAUSIBLE_READING_DECREASE {
+                        return Err(SanityError::MeterReadingDecreased(current.tariff, mr));
+                    }
                 }
-                OBIS::InstantaneousActivePowerPlus(l, p) => {
-                    state.lines[l as usize].active_power_plus = Some(f64::from(&p));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gather the voltage sags and swells of all lines.
+    pub fn voltage_event_summary(&self) -> VoltageEventSummary {
+        VoltageEventSummary {
+            sags: self.lines.each_ref().map(|l| l.voltage_sags),
+            swells: self.lines.each_ref().map(|l| l.voltage_swells),
+            phase_count: self.present_lines().count(),
+        }
+    }
+
+    /// Active power imported from the grid, in kW.
+    ///
+    /// The meter names this "delivered", as in delivered to the client.
+    pub fn import_active_power(&self) -> Option<f64> {
+        self.power_delivered
+    }
+
+    /// Active power exported to the grid, e.g. from solar panels, in kW.
+    ///
+    /// The meter names this "received", as in received from the client.
+    pub fn export_active_power(&self) -> Option<f64> {
+        self.power_received
+    }
+
+    /// Whether power flows to the grid, e.g. as solar panels produce more than is consumed.
+    ///
+    /// Yields `None` if the meter did not report the power received.
+    pub fn is_exporting(&self) -> Option<bool> {
+        self.power_received.map(|p| p > 0.0)
+    }
+
+    /// Total energy delivered to the client, summed over Tariff1 and Tariff2, in kWh.
+    ///
+    /// Yields `None` if either tariff was not reported, to avoid silently undercounting.
+    pub fn total_to(&self) -> Option<f64> {
+        self.meterreadings.iter().map(|mr| mr.to).sum()
+    }
+
+    /// Total energy delivered by the client, summed over Tariff1 and Tariff2, in kWh.
+    ///
+    /// Yields `None` if either tariff was not reported, to avoid silently undercounting.
+    pub fn total_by(&self) -> Option<f64> {
+        self.meterreadings.iter().map(|mr| mr.by).sum()
+    }
+
+    /// Active power delivered to the client, summed over the phases, in kW.
+    ///
+    /// Only the lines the meter reported are summed, so single phase meters are covered.
+    /// Yields `None` if no line is reported, or a reported line lacks its active power.
+    /// On a healthy meter this matches `power_delivered`.
+    pub fn total_active_power_plus(&self) -> Option<f64> {
+        self.present_lines().next()?;
+        self.present_lines().map(|(_, l)| l.active_power_plus).sum()
+    }
+
+    /// Active power received from the client, summed over the phases, in kW.
+    ///
+    /// Follows the same policy as `total_active_power_plus`.
+    pub fn total_active_power_neg(&self) -> Option<f64> {
+        self.present_lines().next()?;
+        self.present_lines().map(|(_, l)| l.active_power_neg).sum()
+    }
+
+    /// Iterate over the lines for which the meter reported any value, paired with their line.
+    pub fn present_lines(&self) -> impl core::iter::Iterator<Item = (crate::obis::Line, &Line)> {
+        use crate::obis::Line::*;
+        [Line1, Line2, Line3]
+            .into_iter()
+            .zip(self.lines.iter())
+            .filter(|(_, l)| l.is_present())
+    }
+
+    /// Current above which the breaker opens, in A, as supervised by e-MUCS meters.
+    ///
+    /// Meters that do not limit the current report 999 A.
+    pub fn fuse_supervision_amps(&self) -> Option<u64> {
+        self.fuse_supervision_threshold
+    }
+
+    /// Iterate over the slaves with a known device type or meter reading, paired with their slave.
+    pub fn active_slaves(&self) -> impl core::iter::Iterator<Item = (crate::obis::Slave, &Slave)> {
+        use crate::obis::Slave::*;
+        [Slave1, Slave2, Slave3, Slave4]
+            .into_iter()
+            .zip(self.slaves.iter())
+            .filter(|(_, s)| s.device_type.is_some() || s.meter_reading.is_some())
+    }
+
+    /// Convert the state to a JSON object keyed by register name.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("State is always representable as JSON")
+    }
+
+    /// Serialize the state as JSON into `buf`, without requiring an allocator.
+    ///
+    /// Fails when `buf` is too small to hold the JSON.
+    #[cfg(feature = "json")]
+    pub fn to_json<'b>(&self, buf: &'b mut [u8]) -> crate::Result<&'b str> {
+        let len = serde_json_core::to_slice(self, buf).map_err(|_| crate::Error::InvalidFormat)?;
+        core::str::from_utf8(&buf[..len]).map_err(|_| crate::Error::InvalidFormat)
+    }
+}
+
+/// A rolling log of the `N` most recent states, e.g. to keep a window over recent telegrams.
+///
+/// Once full, pushing a state evicts the oldest one.
+#[derive(Default)]
+pub struct TelegramLog<const N: usize> {
+    states: heapless::Deque<State, N>,
+}
+
+impl<const N: usize> TelegramLog<N> {
+    pub fn new() -> Self {
+        TelegramLog {
+            states: heapless::Deque::new(),
+        }
+    }
+
+    /// Append a state to the log, yielding the evicted oldest state if the log was full.
+    pub fn push(&mut self, state: State) -> Option<State> {
+        let evicted = if self.states.is_full() {
+            self.states.pop_front()
+        } else {
+            None
+        };
+
+        // Cannot fail, as room was made above.
+        let _ = self.states.push_back(state);
+        evicted
+    }
+
+    /// Iterate over the logged states, from oldest to most recent.
+    pub fn iter(&self) -> impl core::iter::Iterator<Item = &State> {
+        self.states.iter()
+    }
+
+    /// The most recently pushed state.
+    pub fn latest(&self) -> Option<&State> {
+        self.states.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+impl State {
+    /// Apply the objects of a telegram on top of this state.
+    ///
+    /// Fields the telegram does not mention keep their previous value, so objects that are
+    /// only emitted occasionally are retained. The state is left untouched when the telegram
+    /// fails to parse.
+    pub fn merge(&mut self, t: &crate::Telegram<'_>) -> crate::Result<()> {
+        let mut next = self.clone();
+        for o in t.objects() {
+            next.apply(o?)?;
+        }
+        *self = next;
+        Ok(())
+    }
+
+    /// Reset all fields to their defaults in place, including those of the lines and slaves.
+    ///
+    /// Useful to discard accumulated state, for example after the meter has been replaced.
+    pub fn clear(&mut self) {
+        *self = State::default();
+    }
+}
+
+impl core::fmt::Display for State {
+    /// Writes each known register on its own line, with units where applicable.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(tst) = &self.datetime {
+            writeln!(f, "datetime: {}", tst)?;
+        }
+        for TariffReading {
+            tariff,
+            reading: mr,
+        } in self.readings()
+        {
+            let t = tariff as u8 + 1;
+            if let Some(to) = mr.to {
+                writeln!(f, "meter reading to tariff {}: {} kWh", t, to)?;
+            }
+            if let Some(by) = mr.by {
+                writeln!(f, "meter reading by tariff {}: {} kWh", t, by)?;
+            }
+        }
+        if let Some(ti) = self.tariff_indicator {
+            writeln!(f, "tariff indicator: {}", u16::from_be_bytes(ti))?;
+        }
+        if let Some((tst, p)) = &self.maximum_demand_month {
+            writeln!(f, "maximum demand this month: {} kW at {}", p, tst)?;
+        }
+        if let Some(p) = self.power_delivered {
+            writeln!(f, "power delivered: {} kW", p)?;
+        }
+        if let Some(p) = self.power_received {
+            writeln!(f, "power received: {} kW", p)?;
+        }
+        if let Some(n) = self.power_failures {
+            writeln!(f, "power failures: {}", n)?;
+        }
+        if let Some(n) = self.long_power_failures {
+            writeln!(f, "long power failures: {}", n)?;
+        }
+        if let Some(sp) = self.switch_position {
+            writeln!(f, "switch position: {}", sp)?;
+        }
+        if let Some(a) = self.fuse_supervision_threshold {
+            writeln!(f, "fuse supervision threshold: {} A", a)?;
+        }
+        for (i, line) in self.lines.iter().enumerate() {
+            let l = i + 1;
+            if let Some(n) = line.voltage_sags {
+                writeln!(f, "voltage sags L{}: {}", l, n)?;
+            }
+            if let Some(n) = line.voltage_swells {
+                writeln!(f, "voltage swells L{}: {}", l, n)?;
+            }
+            if let Some(v) = line.voltage {
+                writeln!(f, "voltage L{}: {} V", l, v)?;
+            }
+            if let Some(a) = line.current {
+                writeln!(f, "current L{}: {} A", l, a)?;
+            }
+            if let Some(p) = line.active_power_plus {
+                writeln!(f, "active power plus L{}: {} kW", l, p)?;
+            }
+            if let Some(p) = line.active_power_neg {
+                writeln!(f, "active power neg L{}: {} kW", l, p)?;
+            }
+        }
+        for (i, slave) in self.slaves.iter().enumerate() {
+            let s = i + 1;
+            // The unit is left out when the slave does not report it.
+            let unit = slave.unit.as_ref().map_or("", Unit::as_str);
+            let sep = if unit.is_empty() { "" } else { " " };
+            if let Some(dt) = slave.device_type {
+                writeln!(f, "device type slave {}: {}", s, dt)?;
+            }
+            if let Some((tst, mr)) = &slave.meter_reading {
+                writeln!(
+                    f,
+                    "meter reading slave {}: {}{}{} at {}",
+                    s, mr, sep, unit, tst
+                )?;
+            }
+            if let Some((tst, mr)) = &slave.meter_reading_noncorrected {
+                writeln!(
+                    f,
+                    "non-corrected meter reading slave {}: {}{}{} at {}",
+                    s, mr, sep, unit, tst
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The names of the `State` fields, in the order of their bits in a `ChangeSet`.
+const FIELDS: [&str; 12] = [
+    "datetime",
+    "meterreadings",
+    "tariff_indicator",
+    "maximum_demand_month",
+    "power_delivered",
+    "power_received",
+    "power_failures",
+    "long_power_failures",
+    "switch_position",
+    "fuse_supervision_threshold",
+    "lines",
+    "slaves",
+];
+
+/// The fields of a `State` that differ from a previous state, see `State::changed_since`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeSet(u16);
+
+impl ChangeSet {
+    /// Whether the field with the given name changed.
+    pub fn contains(&self, field: &str) -> bool {
+        FIELDS
+            .iter()
+            .position(|f| *f == field)
+            .is_some_and(|i| self.0 & (1 << i) != 0)
+    }
+
+    /// Iterate over the names of the changed fields.
+    pub fn iter(&self) -> impl core::iter::Iterator<Item = &'static str> + '_ {
+        FIELDS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.0 & (1 << i) != 0)
+            .map(|(_, f)| *f)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl State {
+    /// The fields that differ from a previous state.
+    ///
+    /// A field turning from `None` into `Some` counts as a change, equal values do not.
+    /// Nested registers are compared as a whole, thus a change on any line flags `lines`.
+    pub fn changed_since(&self, prev: &State) -> ChangeSet {
+        let changed = [
+            self.datetime != prev.datetime,
+            self.meterreadings != prev.meterreadings,
+            self.tariff_indicator != prev.tariff_indicator,
+            self.maximum_demand_month != prev.maximum_demand_month,
+            self.power_delivered != prev.power_delivered,
+            self.power_received != prev.power_received,
+            self.power_failures != prev.power_failures,
+            self.long_power_failures != prev.long_power_failures,
+            self.switch_position != prev.switch_position,
+            self.fuse_supervision_threshold != prev.fuse_supervision_threshold,
+            self.lines != prev.lines,
+            self.slaves != prev.slaves,
+        ];
+
+        ChangeSet(
+            changed
+                .iter()
+                .enumerate()
+                .fold(0, |bits, (i, c)| bits | (u16::from(*c) << i)),
+        )
+    }
+}
+
+/// A state that is built up from the objects of a telegram, one object at a time.
+pub trait MeterState: Default {
+    /// The kind of object the state is built from.
+    type Obis<'a>;
+
+    /// Apply a single object to the state, overwriting the fields it covers.
+    fn apply(&mut self, o: Self::Obis<'_>) -> crate::Result<()>;
+}
+
+impl MeterState for State {
+    type Obis<'a> = OBIS<'a>;
+
+    fn apply(&mut self, o: OBIS<'_>) -> crate::Result<()> {
+        match o {
+            OBIS::DateTime(tst) => {
+                self.datetime = Some(tst);
+            }
+            OBIS::MeterReadingTo(t, mr) => {
+                self.meterreadings[t as usize].to = Some(f64::from(&mr));
+            }
+            OBIS::MeterReadingBy(t, mr) => {
+                self.meterreadings[t as usize].by = Some(f64::from(&mr));
+            }
+            OBIS::TariffIndicator(ti) => {
+                let mut buf = [0u8; 2];
+                let mut octets = ti.as_octets();
+                buf[0] = octets.next().unwrap_or(Err(crate::Error::InvalidFormat))?;
+                buf[1] = octets.next().unwrap_or(Err(crate::Error::InvalidFormat))?;
+
+                self.tariff_indicator = Some(buf);
+            }
+            OBIS::PowerDelivered(p) => {
+                self.power_delivered = Some(f64::from(&p));
+            }
+            OBIS::PowerReceived(p) => {
+                self.power_received = Some(f64::from(&p));
+            }
+            OBIS::PowerFailures(UFixedInteger(pf)) => {
+                self.power_failures = Some(pf);
+            }
+            OBIS::LongPowerFailures(UFixedInteger(lpf)) => {
+                self.long_power_failures = Some(lpf);
+            }
+            OBIS::MaximumDemandMonth(tst, p) => {
+                self.maximum_demand_month = Some((tst, f64::from(&p)));
+            }
+            OBIS::BreakerState(UFixedInteger(sp)) => {
+                self.switch_position = Some(sp);
+            }
+            OBIS::FuseSupervisionThreshold(UFixedInteger(a)) => {
+                self.fuse_supervision_threshold = Some(a);
+            }
+            OBIS::VoltageSags(l, UFixedInteger(n)) => {
+                self.lines[l as usize].voltage_sags = Some(n);
+            }
+            OBIS::VoltageSwells(l, UFixedInteger(n)) => {
+                self.lines[l as usize].voltage_swells = Some(n);
+            }
+            OBIS::InstantaneousVoltage(l, v) => {
+                self.lines[l as usize].voltage = Some(f64::from(&v));
+            }
+            OBIS::InstantaneousCurrent(l, a) => {
+                self.lines[l as usize].current = Some(f64::from(&a));
+            }
+            OBIS::InstantaneousActivePowerPlus(l, p) => {
+                self.lines[l as usize].active_power_plus = Some(f64::from(&p));
+            }
+            OBIS::InstantaneousActivePowerNeg(l, p) => {
+                self.lines[l as usize].active_power_neg = Some(f64::from(&p));
+            }
+            OBIS::SlaveDeviceType(s, value_x) => {
+                if let Some(UFixedInteger(dt)) = value_x {
+                    self.slaves[s as usize].device_type = Some(dt);
+                } else {
+                    self.slaves[s as usize].device_type = None;
                 }
-                OBIS::InstantaneousActivePowerNeg(l, p) => {
-                    state.lines[l as usize].active_power_neg = Some(f64::from(&p));
+            }
+            OBIS::SlaveMeterReading(s, tst, mr, unit) => {
+                if let Some(mr_value) = mr {
+                    self.slaves[s as usize].meter_reading = Some((tst, f64::from(&mr_value)));
+                } else {
+                    self.slaves[s as usize].meter_reading = None;
                 }
-                OBIS::SlaveDeviceType(s, value_x) => {
-                    if let Some(UFixedInteger(dt)) = value_x {
-                        state.slaves[s as usize].device_type = Some(dt);
-                    } else {
-                        state.slaves[s as usize].device_type = None;
-                    }
+                if unit.is_some() {
+                    self.slaves[s as usize].unit = unit;
                 }
-                OBIS::SlaveMeterReading(s, tst, mr) => {
-                    if let Some(mr_value) = mr {
-                        state.slaves[s as usize].meter_reading = Some((tst, f64::from(&mr_value)));
-                    } else {
-                        state.slaves[s as usize].meter_reading = None;
-                    }
+            }
+            OBIS::SlaveMeterReadingNonCorrected(s, tst, mr, unit) => {
+                self.slaves[s as usize].meter_reading_noncorrected =
+                    mr.map(|mr| (tst, f64::from(&mr)));
+                if unit.is_some() {
+                    self.slaves[s as usize].unit = unit;
                 }
-                _ => {} // Ignore rest.
             }
+            _ => {} // Ignore rest.
+        }
+        Ok(())
+    }
+}
+
+impl<'a> core::convert::TryFrom<&crate::Telegram<'a>> for State {
+    type Error = crate::Error;
+
+    fn try_from(t: &crate::Telegram<'a>) -> Result<Self, Self::Error> {
+        t.objects().try_fold(State::default(), |mut state, o| {
+            state.apply(o?)?;
             Ok(state)
         })
     }
@@ -129,6 +754,66 @@ impl<'a> core::convert::From<&crate::Telegram<'a>> for crate::Result<State> {
     }
 }
 
+/// Separate states for the meters in a log interleaving telegrams of several meters.
+///
+/// Meters are told apart by their equipment identifier, and telegrams without one are
+/// accumulated in a default bucket. The state of a meter is kept up to date with every
+/// telegram of that meter, so objects only emitted occasionally are retained.
+#[derive(Default)]
+pub struct StateAggregator<const N: usize> {
+    states: heapless::Vec<(Option<heapless::String<96>>, State), N>,
+}
+
+impl<const N: usize> StateAggregator<N> {
+    pub fn new() -> Self {
+        StateAggregator {
+            states: heapless::Vec::new(),
+        }
+    }
+
+    /// Apply a telegram to the state of its meter.
+    ///
+    /// Meters are keyed by their decoded equipment identifier, or by its raw octets in case
+    /// these do not decode as ASCII.
+    ///
+    /// Yields `false` when the telegram stems from a new meter while all `N` states are taken.
+    /// The states are left untouched when the telegram fails to parse.
+    pub fn push(&mut self, t: &crate::Telegram<'_>) -> crate::Result<bool> {
+        use core::fmt::Write;
+
+        // Only the identifier is parsed up front, the other objects once applied.
+        let mut equipment_id = None;
+        if let Some(OBIS::EquipmentIdentifier(id)) = t.find_object("0-0:96.1.1").transpose()? {
+            let mut key = heapless::String::new();
+            let mut buf = [0u8; 96];
+            match id.decode_ascii_into(&mut buf) {
+                Ok(decoded) => key.push_str(decoded),
+                Err(_) => write!(key, "{}", id).map_err(|_| ()),
+            }
+            .map_err(|_| crate::Error::InvalidFormat)?;
+            equipment_id = Some(key);
+        }
+
+        match self.states.iter_mut().find(|(id, _)| *id == equipment_id) {
+            Some((_, state)) => state.merge(t)?,
+            None => {
+                let state = State::try_from(t)?;
+                if self.states.push((equipment_id, state)).is_err() {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Iterate over the state of each meter by its equipment identifier, in order of appearance.
+    pub fn states(&self) -> impl core::iter::Iterator<Item = (Option<&str>, &State)> {
+        self.states
+            .iter()
+            .map(|(id, state)| (id.as_ref().map(|id| id.as_str()), state))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -139,7 +824,10 @@ mod tests {
         let (left, _right) = buffer.split_at_mut(file.len());
         left.copy_from_slice(file.as_slice());
 
-        let readout = crate::Readout { buffer };
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
         let telegram = &readout.to_telegram().unwrap();
         let state: super::State = telegram.try_into().unwrap();
 
@@ -162,6 +850,592 @@ mod tests {
         assert_eq!(state.meterreadings[Tariff2 as usize].to.unwrap(), 465.162);
         assert_eq!(state.tariff_indicator.unwrap(), [0, 2]);
 
+        assert_eq!(state.import_active_power(), Some(0.193));
+        assert_eq!(state.export_active_power(), Some(0.0));
+        assert_eq!(state.total_to(), Some(576.239 + 465.162));
+        assert_eq!(state.total_by(), Some(0.0));
+
+        let readings: std::vec::Vec<_> = state
+            .readings()
+            .map(|r| (r.tariff, r.reading.to.unwrap()))
+            .collect();
+        assert_eq!(readings, [(Tariff1, 576.239), (Tariff2, 465.162)]);
+
+        let gas = &state.slaves[crate::obis::Slave::Slave1 as usize];
+        assert_eq!(
+            gas.reading_age_seconds(state.datetime.as_ref().unwrap()),
+            Some(240)
+        );
+        assert_eq!(
+            state.slaves[crate::obis::Slave::Slave2 as usize]
+                .reading_age_seconds(state.datetime.as_ref().unwrap()),
+            None
+        );
+
+        let lines: std::vec::Vec<_> = state.present_lines().map(|(l, _)| l).collect();
+        assert_eq!(lines, [crate::obis::Line::Line1]);
+
+        assert_eq!(state.sanity_check(), Ok(()));
+
+        assert_eq!(
+            state.voltage_event_summary(),
+            super::VoltageEventSummary {
+                sags: [Some(6), None, None],
+                swells: [Some(1), None, None],
+                phase_count: 1,
+            }
+        );
+
         eprintln!("{:?}", state);
     }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn to_json_value() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/isk.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = readout.to_telegram().unwrap();
+        let value = telegram.to_json_value().unwrap();
+
+        assert_eq!(value["prefix"], "ISK");
+        assert_eq!(value["state"]["power_delivered"], 0.193);
+        assert_eq!(value["state"]["meterreadings"][0]["to"], 576.239);
+        #[cfg(not(feature = "serde-rfc3339"))]
+        assert_eq!(value["state"]["datetime"]["year"], 19);
+        #[cfg(feature = "serde-rfc3339")]
+        assert_eq!(value["state"]["datetime"], "2019-03-20T18:14:03+01:00");
+    }
+
+    #[test]
+    fn noncorrected_reading() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/noncorrected.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = &readout.to_telegram().unwrap();
+        let state: super::State = telegram.try_into().unwrap();
+
+        let gas = &state.slaves[crate::obis::Slave::Slave1 as usize];
+        assert_eq!(gas.meter_reading.as_ref().unwrap().1, 304.089);
+        assert_eq!(gas.meter_reading_noncorrected.as_ref().unwrap().1, 305.123);
+    }
+
+    #[test]
+    fn single_phase() {
+        let mut buffer = [0u8; 2048];
+        let file = std::fs::read("test/isk.txt").unwrap();
+
+        let (left, _right) = buffer.split_at_mut(file.len());
+        left.copy_from_slice(file.as_slice());
+
+        let readout = crate::Readout {
+            buffer,
+            len: file.len(),
+        };
+        let telegram = &readout.to_telegram().unwrap();
+        let state: super::State = telegram.try_into().unwrap();
+
+        use crate::obis::Line::*;
+        assert_eq!(state.lines[Line1 as usize].current, Some(1.0));
+        assert_eq!(state.lines[Line1 as usize].voltage, Some(236.1));
+        assert!(!state.lines[Line2 as usize].is_present());
+        assert!(!state.lines[Line3 as usize].is_present());
+    }
+
+    #[test]
+    fn telegram_log() {
+        let state = |pf| super::State {
+            power_failures: Some(pf),
+            ..Default::default()
+        };
+
+        let mut log = super::TelegramLog::<3>::new();
+        assert!(log.is_empty());
+        assert!(log.latest().is_none());
+
+        for pf in 0..3 {
+            assert!(log.push(state(pf)).is_none());
+        }
+        assert_eq!(log.push(state(3)).unwrap().power_failures, Some(0));
+        assert_eq!(log.push(state(4)).unwrap().power_failures, Some(1));
+
+        let logged: std::vec::Vec<_> = log.iter().map(|s| s.power_failures.unwrap()).collect();
+        assert_eq!(logged, [2, 3, 4]);
+        assert_eq!(log.latest().unwrap().power_failures, Some(4));
+        assert_eq!(log.len(), 3);
+    }
+
+    #[test]
+    fn state_aggregator() {
+        let telegram = |buffer: &mut [u8; 2048], path| {
+            let file = std::fs::read(path).unwrap();
+            buffer.fill(0);
+            buffer[..file.len()].copy_from_slice(file.as_slice());
+            file.len()
+        };
+
+        let mut aggregator = super::StateAggregator::<2>::new();
+        let mut buffer = [0u8; 2048];
+        for path in ["test/isk.txt", "test/kaifa.txt", "test/noncorrected.txt"] {
+            let len = telegram(&mut buffer, path);
+            let readout = crate::Readout { buffer, len };
+            assert!(aggregator.push(&readout.to_telegram().unwrap()).unwrap());
+        }
+
+        let len = telegram(&mut buffer, "test/mcs.txt");
+        let readout = crate::Readout { buffer, len };
+        assert!(!aggregator.push(&readout.to_telegram().unwrap()).unwrap());
+
+        let states: std::vec::Vec<_> = aggregator.states().collect();
+        assert_eq!(states.len(), 2);
+
+        let (id, isk) = states[0];
+        assert_eq!(id, Some("E0043007052870318"));
+        let gas = &isk.slaves[crate::obis::Slave::Slave1 as usize];
+        assert_eq!(gas.meter_reading_noncorrected.as_ref().unwrap().1, 305.123);
+
+        let (id, kaifa) = states[1];
+        assert_eq!(id, Some("E0026000024153615"));
+        assert!(kaifa.slaves[crate::obis::Slave::Slave1 as usize]
+            .meter_reading_noncorrected
+            .is_none());
+    }
+
+    #[test]
+    fn state_aggregator_invalid() {
+        let mut aggregator = super::StateAggregator::<2>::new();
+
+        // The tariff indicator only fails once applied, after the meter has been identified.
+        let file = b"/ISK5\\2M550E-1012\r\n\r\n0-0:96.1.1(4530)\r\n0-0:96.14.0(zz02)\r\n!";
+        let invalid = crate::BorrowedReadout(file)
+            .to_telegram_unchecked()
+            .unwrap();
+        assert!(aggregator.push(&invalid).is_err());
+        assert!(aggregator.states().next().is_none());
+    }
+
+    #[test]
+    fn device_type_kind() {
+        let slave = |device_type| super::Slave {
+            device_type,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            slave(Some(3)).device_type_kind(),
+            Some(super::DeviceType::Gas)
+        );
+        assert_eq!(
+            slave(Some(7)).device_type_kind(),
+            Some(super::DeviceType::Water)
+        );
+        assert_eq!(
+            slave(Some(42)).device_type_kind(),
+            Some(super::DeviceType::Unknown(42))
+        );
+        assert_eq!(slave(Some(256)).device_type_kind(), None);
+        assert_eq!(slave(None).device_type_kind(), None);
+        assert_eq!(super::DeviceType::Water.code(), 7);
+
+        let file = std::fs::read("test/water.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+        let kinds: std::vec::Vec<_> = state.slaves.iter().map(|s| s.device_type_kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                Some(super::DeviceType::Gas),
+                Some(super::DeviceType::Water),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn maximum_demand_month() {
+        let file = std::fs::read("test/flu.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        assert_eq!(telegram.prefix, "FLU");
+
+        let state: super::State = (&telegram).try_into().unwrap();
+        assert_eq!(state.fuse_supervision_amps(), Some(999));
+        assert_eq!(state.power_received, Some(0.0));
+        assert_eq!(state.is_exporting(), Some(false));
+        assert_eq!(
+            state.lines[crate::obis::Line::Line1 as usize].current,
+            Some(1.25)
+        );
+        let (tst, peak) = state.maximum_demand_month.unwrap();
+        assert_eq!(tst, crate::types::TST::parse("(200509134558S)").unwrap());
+        assert_eq!(peak, 2.589);
+
+        let gas = &state.slaves[crate::obis::Slave::Slave1 as usize];
+        assert_eq!(gas.meter_reading_noncorrected.as_ref().unwrap().1, 112.384);
+    }
+
+    #[test]
+    fn sanity_check() {
+        use super::{SanityError, State};
+        use crate::obis::Line::*;
+
+        let mut state = State::default();
+        assert_eq!(state.sanity_check(), Ok(()));
+
+        assert_eq!(state.total_to(), None);
+        state.meterreadings[0].to = Some(1.0);
+        assert_eq!(state.total_to(), None);
+
+        state.lines[Line2 as usize].voltage = Some(23.1);
+        assert_eq!(state.sanity_check(), Err(SanityError::Voltage(Line2, 23.1)));
+
+        state.power_delivered = Some(0.5);
+        state.power_received = Some(0.1);
+        assert_eq!(state.sanity_check(), Err(SanityError::SimultaneousPower));
+    }
+
+    #[test]
+    fn sanity_check_fuse_supervision() {
+        use super::{SanityError, State};
+        use crate::obis::Line::*;
+
+        let mut state = State::default();
+        state.lines[Line1 as usize].current = Some(30.0);
+        assert_eq!(state.sanity_check(), Ok(()));
+
+        state.fuse_supervision_threshold = Some(25);
+        assert_eq!(state.sanity_check(), Ok(()));
+
+        state.fuse_supervision_threshold = Some(16);
+        assert_eq!(
+            state.sanity_check(),
+            Err(SanityError::CurrentAboveThreshold(Line1, 30.0))
+        );
+    }
+
+    #[test]
+    fn sanity_check_negative_reading() {
+        use super::{SanityError, State};
+        use crate::obis::Tariff::*;
+
+        let mut state = State::default();
+        state.meterreadings[Tariff2 as usize].by = Some(-1.5);
+        assert_eq!(
+            state.sanity_check(),
+            Err(SanityError::NegativeMeterReading(Tariff2, -1.5))
+        );
+    }
+
+    #[test]
+    fn sanity_check_against() {
+        use super::{SanityError, State};
+        use crate::obis::Tariff::*;
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let previous: State = (&telegram).try_into().unwrap();
+
+        // The partial telegram rounds the reading of 576.239 kWh to 576.24 kWh.
+        let file = std::fs::read("test/partial.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let mut state: State = (&telegram).try_into().unwrap();
+        assert_eq!(state.sanity_check_against(&previous), Ok(()));
+        assert_eq!(previous.sanity_check_against(&state), Ok(()));
+
+        state.meterreadings[Tariff1 as usize].to = Some(57.624);
+        assert_eq!(
+            state.sanity_check_against(&previous),
+            Err(SanityError::MeterReadingDecreased(Tariff1, 57.624))
+        );
+    }
+
+    #[test]
+    fn meter_state() {
+        use super::{MeterState, State};
+
+        fn ingest<'a, S>(t: &crate::Telegram<'a>) -> crate::Result<S>
+        where
+            S: MeterState<Obis<'a> = crate::OBIS<'a>>,
+        {
+            t.objects().try_fold(S::default(), |mut state, o| {
+                state.apply(o?)?;
+                Ok(state)
+            })
+        }
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+
+        let state: State = ingest(&telegram).unwrap();
+        let expected: State = (&telegram).try_into().unwrap();
+        assert_eq!(state.datetime, expected.datetime);
+        assert_eq!(state.meterreadings[0].to, Some(576.239));
+        assert_eq!(state.power_delivered, expected.power_delivered);
+        assert_eq!(state.slaves[0].device_type, Some(3));
+    }
+
+    #[test]
+    fn merge() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let mut state: super::State = (&telegram).try_into().unwrap();
+        let datetime = crate::types::TST::parse("(190320181403W)").unwrap();
+        assert_eq!(state.datetime, Some(datetime));
+
+        let file = std::fs::read("test/partial.txt").unwrap();
+        let partial = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        assert!(!partial
+            .objects()
+            .any(|o| matches!(o, Ok(crate::OBIS::DateTime(_)))));
+
+        state.merge(&partial).unwrap();
+        assert_eq!(
+            state.datetime,
+            Some(crate::types::TST::parse("(190320181403W)").unwrap())
+        );
+        assert_eq!(state.power_delivered, Some(0.201));
+        assert_eq!(state.meterreadings[0].to, Some(576.24));
+    }
+
+    #[test]
+    fn merge_invalid() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let mut state: super::State = (&telegram).try_into().unwrap();
+
+        // The tariff indicator only fails once applied, after the power delivered.
+        let file = b"/ISK5\\2M550E-1012\r\n\r\n1-0:1.7.0(00.201*kW)\r\n0-0:96.14.0(zz02)\r\n!";
+        let invalid = crate::BorrowedReadout(file)
+            .to_telegram_unchecked()
+            .unwrap();
+        assert!(state.merge(&invalid).is_err());
+
+        assert_eq!(state.power_delivered, Some(0.193));
+        assert_eq!(state, (&telegram).try_into().unwrap());
+    }
+
+    #[test]
+    fn changed_since() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let prev: super::State = (&telegram).try_into().unwrap();
+        let mut state: super::State = (&telegram).try_into().unwrap();
+        assert!(state.changed_since(&prev).is_empty());
+
+        state.power_delivered = Some(0.2);
+        let changes = state.changed_since(&prev);
+        assert_eq!(changes.len(), 1);
+        assert!(changes.contains("power_delivered"));
+        assert!(!changes.contains("datetime"));
+        assert_eq!(
+            changes.iter().collect::<std::vec::Vec<_>>(),
+            ["power_delivered"]
+        );
+
+        let empty = super::State::default();
+        assert!(state.changed_since(&empty).contains("datetime"));
+    }
+
+    #[test]
+    fn clear() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let mut state: super::State = (&telegram).try_into().unwrap();
+        assert_ne!(state, super::State::default());
+
+        state.clear();
+        assert_eq!(state, super::State::default());
+    }
+
+    #[test]
+    fn display() {
+        use std::string::ToString;
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+        let output = state.to_string();
+
+        let lines: std::vec::Vec<_> = output.lines().collect();
+        assert!(lines.contains(&"power delivered: 0.193 kW"));
+        assert!(lines.contains(&"meter reading to tariff 1: 576.239 kWh"));
+        assert!(lines.contains(&"datetime: 2019-03-20T18:14:03+01:00"));
+        assert!(lines.contains(&"meter reading slave 1: 304.089 m3 at 2019-03-20T18:10:03+01:00"));
+        assert!(!output.contains("L2"));
+        assert_eq!(super::State::default().to_string(), "");
+    }
+
+    #[test]
+    fn total_active_power() {
+        use crate::obis::Line::*;
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let mut state: super::State = (&telegram).try_into().unwrap();
+        assert_eq!(state.total_active_power_plus(), state.power_delivered);
+        assert_eq!(state.total_active_power_plus(), Some(0.193));
+        assert_eq!(state.total_active_power_neg(), Some(0.0));
+
+        state.lines[Line2 as usize].active_power_plus = Some(0.25);
+        assert_eq!(state.total_active_power_plus(), Some(0.193 + 0.25));
+        assert_eq!(state.total_active_power_neg(), None);
+
+        assert_eq!(super::State::default().total_active_power_plus(), None);
+    }
+
+    #[test]
+    fn lf_line_endings() {
+        let file = std::fs::read("test/isk_lf.txt").unwrap();
+        assert!(!file.contains(&b'\r'));
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let expected: super::State = (&telegram).try_into().unwrap();
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn profile_reading() {
+        let file = std::fs::read("test/dsmr3.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file)
+            .to_telegram_unchecked()
+            .unwrap();
+        assert_eq!(telegram.objects().count(), 9);
+
+        let state: super::State = (&telegram).try_into().unwrap();
+        let (tst, mr) = state.slaves[0].meter_reading.as_ref().unwrap();
+        assert_eq!(*tst, crate::types::TST::parse("(121209190000W)").unwrap());
+        assert_eq!(*mr, 123.456);
+        assert_eq!(state.power_received, Some(0.0));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_json() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+
+        let mut buf = [0u8; 2048];
+        let json = state.to_json(&mut buf).unwrap();
+        assert!(json.starts_with('{'));
+        assert!(json.contains("\"power_delivered\":0.193"));
+
+        let mut buf = [0u8; 16];
+        assert!(state.to_json(&mut buf).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn tariff_reading_json() {
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+
+        let reading = state.readings().nth(1).unwrap();
+        let mut buf = [0u8; 128];
+        let len = serde_json_core::to_slice(&reading, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"tariff":"Tariff2","reading":{"to":465.162,"by":0.0}}"#
+        );
+    }
+
+    #[test]
+    fn stale_slave_reading() {
+        use crate::types::TST;
+
+        let file = std::fs::read("test/isk.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let mut state: super::State = (&telegram).try_into().unwrap();
+
+        // A later telegram lacking a gas reading leaves the capture time as is.
+        let file = std::fs::read("test/partial.txt").unwrap();
+        state
+            .merge(&crate::BorrowedReadout(&file).to_telegram().unwrap())
+            .unwrap();
+
+        let gas = &state.slaves[crate::obis::Slave::Slave1 as usize];
+        let now = TST::parse("(190320182003W)").unwrap();
+        assert_eq!(gas.reading_age_against(&now), Some(600));
+
+        let summer = TST::parse("(190320192003S)").unwrap();
+        assert_eq!(gas.reading_age_against(&summer), Some(600));
+    }
+
+    #[test]
+    fn sparse_lines() {
+        use crate::obis::Line::*;
+
+        let file = std::fs::read("test/sparse.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+
+        let lines: std::vec::Vec<_> = state.present_lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0, Line1);
+        assert_eq!(lines[0].1.voltage, Some(231.2));
+        assert_eq!(lines[1].0, Line3);
+        assert_eq!(lines[1].1.current, Some(1.0));
+        assert!(!state.lines[Line2 as usize].is_present());
+
+        assert_eq!(state.voltage_event_summary().phase_count, 2);
+        assert_eq!(state.total_active_power_plus(), Some(0.7));
+    }
+
+    #[test]
+    fn fractional_current() {
+        use crate::obis::Line::*;
+
+        let file = std::fs::read("test/amps.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+
+        assert_eq!(state.lines[Line1 as usize].current, Some(1.23));
+        assert_eq!(state.lines[Line2 as usize].current, Some(0.87));
+        assert_eq!(state.lines[Line3 as usize].current, Some(2.0));
+        assert_eq!(state.sanity_check(), Ok(()));
+    }
+
+    #[test]
+    fn active_slaves() {
+        let file = std::fs::read("test/mcs.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+        assert_eq!(state.active_slaves().count(), 0);
+
+        let file = std::fs::read("test/water.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let state: super::State = (&telegram).try_into().unwrap();
+        let slaves: std::vec::Vec<_> = state.active_slaves().map(|(s, _)| s).collect();
+        assert_eq!(
+            slaves,
+            [crate::obis::Slave::Slave1, crate::obis::Slave::Slave2]
+        );
+    }
+
+    #[test]
+    fn is_exporting() {
+        let mut state = super::State::default();
+        assert_eq!(state.is_exporting(), None);
+
+        state.power_received = Some(1.5);
+        assert_eq!(state.is_exporting(), Some(true));
+    }
 }
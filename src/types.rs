@@ -8,6 +8,7 @@ use crate::{Error, Result};
 
 /// Octet strings as defined by tag 9.
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct OctetString<'a>(&'a str);
 
 impl<'a> OctetString<'a> {
@@ -34,10 +35,82 @@ impl<'a> OctetString<'a> {
             u8::from_str_radix(&self.0[i * 2..=i * 2 + 1], 16).map_err(|_| Error::InvalidFormat)
         })
     }
+
+    /// Decode the octets into `buf`, yielding them as a string.
+    ///
+    /// Fails when `buf` is too small or the octets are not valid UTF-8.
+    pub fn decode_into<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str> {
+        if !self.0.len().is_multiple_of(2) {
+            return Err(Error::InvalidFormat);
+        }
+
+        let buf = buf
+            .get_mut(..self.0.len() / 2)
+            .ok_or(Error::InvalidFormat)?;
+        for (b, octet) in buf.iter_mut().zip(self.as_octets()) {
+            *b = octet?;
+        }
+
+        core::str::from_utf8(buf).map_err(|_| Error::InvalidFormat)
+    }
+
+    /// Decode the octets of an identifier into `buf`, such as the equipment identifier.
+    ///
+    /// The buffer fits any octet string of up to 96 octets. Fails when the octets are not ASCII.
+    pub fn decode_ascii_into<'b>(&self, buf: &'b mut [u8; 96]) -> Result<&'b str> {
+        let decoded = self.decode_into(buf)?;
+        if !decoded.is_ascii() {
+            return Err(Error::InvalidFormat);
+        }
+
+        Ok(decoded)
+    }
+}
+
+/// Displays the octets as the hexadecimal string found in the telegram.
+impl core::fmt::Display for OctetString<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// The version of the P1 standard the meter adheres to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DsmrVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl DsmrVersion {
+    /// Interpret the octets of a `Version` object.
+    ///
+    /// The first octet encodes the major and minor version as two decimal digits,
+    /// e.g. `50` for DSMR 5.0 and `42` for DSMR 4.2. Any additional octets, such as a patch
+    /// level, are ignored.
+    ///
+    /// Of the meters in the test fixtures the ISK and MCS meters emit the single octet `50`, and
+    /// the Kaifa meter `42`. The FLU (e-MUCS) meter emits no `Version` object at all.
+    pub fn parse(version: &OctetString) -> Result<DsmrVersion> {
+        let first = version.as_octets().next().ok_or(Error::InvalidFormat)??;
+
+        let (major, minor) = (first >> 4, first & 0x0f);
+        if major > 9 || minor > 9 {
+            return Err(Error::InvalidFormat);
+        }
+
+        Ok(DsmrVersion { major, minor })
+    }
 }
 
 /// Timestamps.
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Timestamps compare equal by their fields, see `cmp_instant` to compare the instant they represent.
+///
+/// With the `serde-rfc3339` feature timestamps (de)serialize as an RFC 3339 string instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "serde-rfc3339"), derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TST {
     pub year: u8,
     pub month: u8,
@@ -51,8 +124,13 @@ pub struct TST {
 }
 
 impl TST {
+    /// Parse a timestamp from an OBIS body, i.e. `(YYMMDDhhmmssX)`.
+    ///
+    /// Some meters omit the seconds, i.e. `(YYMMDDhhmmX)`, in which case they are taken to be 0.
     pub fn parse(body: &str) -> Result<TST> {
-        if body.len() < 15 {
+        let short = matches!(body.get(11..=12), Some("S)" | "W)"));
+        let dst = if short { 11 } else { 13 };
+        if body.len() < dst + 2 {
             return Err(Error::InvalidFormat);
         }
 
@@ -64,36 +142,426 @@ impl TST {
             day: parsetwo(5)?,
             hour: parsetwo(7)?,
             minute: parsetwo(9)?,
-            second: parsetwo(11)?,
-            dst: match &body[13..=13] {
+            second: if short { 0 } else { parsetwo(11)? },
+            dst: match &body[dst..=dst] {
                 "S" => Ok(true),
                 "W" => Ok(false),
                 _ => Err(Error::InvalidFormat),
             }?,
         })
     }
+
+    /// Parse a timestamp like `parse`, but also accept one lacking the daylight savings indicator.
+    ///
+    /// Some older meters emit `(YYMMDDhhmmss)`, in which case the timestamp is taken to be winter time.
+    pub fn parse_lenient(body: &str) -> Result<TST> {
+        if body.len() != 14 || !body.ends_with(')') {
+            return TST::parse(body);
+        }
+
+        let mut tst = heapless::String::<15>::new();
+        tst.push_str(body.get(..13).ok_or(Error::InvalidFormat)?)
+            .map_err(|_| Error::InvalidFormat)?;
+        tst.push_str("W)").map_err(|_| Error::InvalidFormat)?;
+        TST::parse(&tst)
+    }
+
+    /// Parse a timestamp as displayed, e.g. `2019-03-20T18:14:03+01:00`.
+    ///
+    /// Only the offsets of Dutch winter and summer time are accepted.
+    pub fn parse_rfc3339(s: &str) -> Result<TST> {
+        let s = s.as_bytes();
+        if s.len() != 25 || &s[..2] != b"20" {
+            return Err(Error::InvalidFormat);
+        }
+
+        for (i, c) in [
+            (4, b'-'),
+            (7, b'-'),
+            (10, b'T'),
+            (13, b':'),
+            (16, b':'),
+            (22, b':'),
+        ] {
+            if s[i] != c {
+                return Err(Error::InvalidFormat);
+            }
+        }
+
+        let parsetwo = |i: usize| match (s[i], s[i + 1]) {
+            (a @ b'0'..=b'9', b @ b'0'..=b'9') => Ok((a - b'0') * 10 + (b - b'0')),
+            _ => Err(Error::InvalidFormat),
+        };
+
+        Ok(TST {
+            year: parsetwo(2)?,
+            month: parsetwo(5)?,
+            day: parsetwo(8)?,
+            hour: parsetwo(11)?,
+            minute: parsetwo(14)?,
+            second: parsetwo(17)?,
+            dst: match &s[19..] {
+                b"+02:00" => Ok(true),
+                b"+01:00" => Ok(false),
+                _ => Err(Error::InvalidFormat),
+            }?,
+        })
+    }
+
+    /// Seconds since the unix epoch, as per the Dutch local time (CET/CEST) of the meter.
+    ///
+    /// Yields `None` when the timestamp does not represent a valid date, such as the
+    /// all-zeroes timestamp some meters emit for absent slave readings.
+    pub fn to_unix(&self) -> Option<i64> {
+        let year = 2000 + i64::from(self.year);
+        if !(1..=12).contains(&self.month)
+            || self.day == 0
+            || self.day > days_in_month(year, self.month)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 59
+        {
+            return None;
+        }
+
+        Some(self.instant())
+    }
+
+    /// Seconds elapsed from `other` until this timestamp, negative if `other` is later.
+    ///
+    /// Yields `None` when either timestamp does not represent a valid date.
+    pub fn difference_seconds(&self, other: &TST) -> Option<i64> {
+        Some(self.to_unix()? - other.to_unix()?)
+    }
+
+    /// Offset of the local time of the meter to UTC, i.e. one hour in winter and two in summer.
+    pub fn utc_offset_seconds(&self) -> i32 {
+        if self.dst {
+            7200
+        } else {
+            3600
+        }
+    }
+
+    /// Shift the timestamp back to UTC, rolling over to the previous day, month or year as needed.
+    ///
+    /// The `dst` flag of the result is cleared, so note that comparing it against timestamps in
+    /// local time does not take the shift into account.
+    pub fn to_utc(&self) -> TST {
+        let offset = (self.utc_offset_seconds() / 3600) as u8;
+        let (mut year, mut month, mut day) = (self.year, self.month, self.day);
+
+        let hour = if self.hour >= offset {
+            self.hour - offset
+        } else {
+            if day > 1 {
+                day -= 1;
+            } else {
+                if month > 1 {
+                    month -= 1;
+                } else {
+                    month = 12;
+                    year = year.wrapping_sub(1);
+                }
+                day = days_in_month(2000 + i64::from(year), month);
+            }
+            self.hour + 24 - offset
+        };
+
+        TST {
+            year,
+            month,
+            day,
+            hour,
+            minute: self.minute,
+            second: self.second,
+            dst: false,
+        }
+    }
+
+    /// Compare chronologically, such that the same instant in winter and summer time compares equal.
+    ///
+    /// Useful to sort timestamps, e.g. `timestamps.sort_by(TST::cmp_instant)`.
+    pub fn cmp_instant(&self, other: &TST) -> core::cmp::Ordering {
+        self.instant().cmp(&other.instant())
+    }
+
+    /// Seconds since the unix epoch without validating the fields, as a key for comparisons.
+    fn instant(&self) -> i64 {
+        let offset = i64::from(self.utc_offset_seconds());
+        let days = days_from_civil(2000 + i64::from(self.year), self.month, self.day);
+
+        days * 86400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second)
+            - offset
+    }
+}
+
+/// Displays the timestamp as ISO 8601, e.g. `2019-03-20T18:14:03+01:00`.
+impl core::fmt::Display for TST {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "20{:02}-{:02}-{:02}T{:02}:{:02}:{:02}+{:02}:00",
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            if self.dst { 2 } else { 1 }
+        )
+    }
+}
+
+#[cfg(feature = "serde-rfc3339")]
+impl Serialize for TST {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde-rfc3339")]
+impl<'de> Deserialize<'de> for TST {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<TST, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = TST;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("an RFC 3339 timestamp")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> core::result::Result<TST, E> {
+                TST::parse_rfc3339(s)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(s), &self))
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// Units suffixed to values, as in `(00.193*kW)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Unit {
+    Kilowatt,
+    KilowattHour,
+    Kilovar,
+    KilovarHour,
+    Volt,
+    Ampere,
+    CubicMeter,
+    Gigajoule,
+    Second,
+}
+
+impl Unit {
+    /// Parse the unit as written in the telegram, e.g. `kWh`.
+    pub fn parse(unit: &str) -> Result<Unit> {
+        Ok(match unit {
+            "kW" => Unit::Kilowatt,
+            "kWh" => Unit::KilowattHour,
+            "kvar" => Unit::Kilovar,
+            "kvarh" => Unit::KilovarHour,
+            "V" => Unit::Volt,
+            "A" => Unit::Ampere,
+            "m3" => Unit::CubicMeter,
+            "GJ" => Unit::Gigajoule,
+            "s" => Unit::Second,
+            _ => return Err(Error::InvalidFormat),
+        })
+    }
+
+    /// The unit as written in the telegram.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Kilowatt => "kW",
+            Unit::KilowattHour => "kWh",
+            Unit::Kilovar => "kvar",
+            Unit::KilovarHour => "kvarh",
+            Unit::Volt => "V",
+            Unit::Ampere => "A",
+            Unit::CubicMeter => "m3",
+            Unit::Gigajoule => "GJ",
+            Unit::Second => "s",
+        }
+    }
+
+    /// Parse the unit suffixed to the value in an OBIS body, such as `(00.193*kW)`.
+    fn parse_suffix(body: &str) -> Result<Unit> {
+        let start = body.find('*').ok_or(Error::InvalidFormat)? + 1;
+        let end = body.find(')').ok_or(Error::InvalidFormat)?;
+        Unit::parse(body.get(start..end).ok_or(Error::InvalidFormat)?)
+    }
+}
+
+impl core::fmt::Display for Unit {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Fixed length unsigned doubles as defined by tag 6.
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UFixedDouble {
     buffer: u64,
     point: u8,
 }
 
+/// Fractional separators accepted when parsing.
+///
+/// The standard prescribes a '.', but some third-party bridges emit a locale-dependent ','.
+const FRACTIONAL_SEPARATORS: [char; 2] = ['.', ','];
+
 impl UFixedDouble {
     pub fn parse(body: &str, length: usize, point: u8) -> Result<UFixedDouble> {
+        if point as usize > length {
+            return Err(Error::InvalidFormat);
+        }
+
         // Do not forget the extra '.'
         let buffer = body.get(1..length + 2).ok_or(Error::InvalidFormat)?;
-        let (upper, lower) = buffer.split_at(length - point as usize);
+        let (upper, lower) = buffer
+            .split_at_checked(length - point as usize)
+            .ok_or(Error::InvalidFormat)?;
+        let lower = lower
+            .strip_prefix(FRACTIONAL_SEPARATORS)
+            .ok_or(Error::InvalidFormat)?;
 
         let upper: u64 = upper.parse().map_err(|_| Error::InvalidFormat)?;
-        let lower: u64 = lower[1..].parse().map_err(|_| Error::InvalidFormat)?;
+        let lower: u64 = lower.parse().map_err(|_| Error::InvalidFormat)?;
 
-        Ok(UFixedDouble {
-            buffer: upper * 10u64.pow(u32::from(point)) + lower,
-            point,
-        })
+        let buffer = 10u64
+            .checked_pow(u32::from(point))
+            .and_then(|scale| upper.checked_mul(scale))
+            .and_then(|upper| upper.checked_add(lower))
+            .ok_or(Error::InvalidFormat)?;
+
+        Ok(UFixedDouble { buffer, point })
+    }
+
+    /// Parse a fixed length double along with its unit.
+    pub fn parse_with_unit(body: &str, length: usize, point: u8) -> Result<(UFixedDouble, Unit)> {
+        let value = UFixedDouble::parse(body, length, point)?;
+        let unit = body
+            .get(length + 2..)
+            .ok_or(Error::InvalidFormat)
+            .and_then(Unit::parse_suffix)?;
+
+        Ok((value, unit))
+    }
+
+    /// Parse a variable length double with a max amount of digits from an OBIS body.
+    ///
+    /// The position of the decimal point is derived from the body itself.
+    pub fn parse_max(body: &str, max_length: usize) -> Result<UFixedDouble> {
+        let end = body.find(['*', ')']).ok_or(Error::InvalidFormat)?;
+        let buffer = body.get(1..end).ok_or(Error::InvalidFormat)?;
+        let period = buffer
+            .find(FRACTIONAL_SEPARATORS)
+            .ok_or(Error::InvalidFormat)?;
+
+        let length = buffer.len() - 1;
+        if length > max_length {
+            return Err(Error::InvalidFormat);
+        }
+
+        UFixedDouble::parse(body, length, (length - period) as u8)
+    }
+
+    /// The raw mantissa and the amount of decimals, for use without floating point arithmetic.
+    pub fn as_scaled(&self) -> (u64, u8) {
+        (self.buffer, self.point)
+    }
+
+    /// The value in thousandths, truncating any further decimals.
+    ///
+    /// Yields `None` when the value in thousandths does not fit a `u64`.
+    pub fn to_millis(&self) -> Option<u64> {
+        match self.point {
+            point @ 0..=3 => self.buffer.checked_mul(10u64.pow(u32::from(3 - point))),
+            point => Some(self.buffer / 10u64.checked_pow(u32::from(point - 3))?),
+        }
+    }
+
+    /// The mantissa scaled to the given amount of decimals, at least `self.point`.
+    fn normalized(&self, point: u8) -> u128 {
+        u128::from(self.buffer) * 10u128.pow(u32::from(point - self.point))
+    }
+}
+
+/// Displays the exact decimal value, e.g. `576.239`.
+impl core::fmt::Display for UFixedDouble {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let scale = 10u64.pow(u32::from(self.point));
+        write!(f, "{}", self.buffer / scale)?;
+        if self.point > 0 {
+            write!(
+                f,
+                ".{:0width$}",
+                self.buffer % scale,
+                width = self.point as usize
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Values compare by magnitude, such that `1.5` equals `1.50`.
+impl PartialEq for UFixedDouble {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == core::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for UFixedDouble {}
+
+impl PartialOrd for UFixedDouble {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UFixedDouble {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        let point = self.point.max(other.point);
+        self.normalized(point).cmp(&other.normalized(point))
     }
 }
 
@@ -103,8 +571,64 @@ impl core::convert::From<&UFixedDouble> for f64 {
     }
 }
 
+/// Fixed length signed doubles, such as the net power some meters report.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SFixedDouble {
+    buffer: i64,
+    point: u8,
+}
+
+impl SFixedDouble {
+    /// Parse a double with an optional leading '-', which does not count towards the length.
+    pub fn parse(body: &str, length: usize, point: u8) -> Result<SFixedDouble> {
+        let (negative, body) = SFixedDouble::strip_sign(body);
+        SFixedDouble::from_unsigned(negative, UFixedDouble::parse(body, length, point)?)
+    }
+
+    /// Parse a variable length double with a max amount of digits from an OBIS body.
+    pub fn parse_max(body: &str, max_length: usize) -> Result<SFixedDouble> {
+        let (negative, body) = SFixedDouble::strip_sign(body);
+        SFixedDouble::from_unsigned(negative, UFixedDouble::parse_max(body, max_length)?)
+    }
+
+    /// Yields whether the body is negative, and the body with the sign taking the place of the '('.
+    fn strip_sign(body: &str) -> (bool, &str) {
+        match body.get(1..) {
+            Some(rest) if rest.starts_with('-') => (true, rest),
+            _ => (false, body),
+        }
+    }
+
+    fn from_unsigned(negative: bool, value: UFixedDouble) -> Result<SFixedDouble> {
+        let buffer = i64::try_from(value.buffer).map_err(|_| Error::InvalidFormat)?;
+
+        Ok(SFixedDouble {
+            buffer: if negative { -buffer } else { buffer },
+            point: value.point,
+        })
+    }
+}
+
+/// Whole numbers, such as currents of meters that do not report fractional amperes.
+impl core::convert::From<UFixedInteger> for UFixedDouble {
+    fn from(other: UFixedInteger) -> Self {
+        UFixedDouble {
+            buffer: other.0,
+            point: 0,
+        }
+    }
+}
+
+impl core::convert::From<&SFixedDouble> for f64 {
+    fn from(other: &SFixedDouble) -> Self {
+        other.buffer as f64 / (10u64.pow(u32::from(other.point)) as f64)
+    }
+}
+
 /// Fixed length unsigned integers as defined by tags 15-21.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UFixedInteger(pub u64);
 
 impl UFixedInteger {
@@ -115,3 +639,355 @@ impl UFixedInteger {
         Ok(UFixedInteger(number))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tst_parse() {
+        let full = TST::parse("(190320181403W)").unwrap();
+        assert_eq!(
+            (full.hour, full.minute, full.second, full.dst),
+            (18, 14, 3, false)
+        );
+
+        let short = TST::parse("(1903201814S)").unwrap();
+        assert_eq!(
+            (short.year, short.month, short.day, short.hour, short.minute),
+            (19, 3, 20, 18, 14)
+        );
+        assert_eq!((short.second, short.dst), (0, true));
+
+        // Malformed bodies of either form are rejected.
+        assert!(TST::parse("(1903201814X)").is_err());
+        assert!(TST::parse("(19032018140W)").is_err());
+        assert!(TST::parse("(190320181403)").is_err());
+    }
+
+    #[test]
+    fn tst_parse_lenient() {
+        let tst = TST::parse_lenient("(190320181403)").unwrap();
+        assert_eq!(tst, TST::parse("(190320181403W)").unwrap());
+        assert!(!tst.dst);
+
+        // Either form carrying an indicator parses as usual.
+        assert!(TST::parse_lenient("(190320181403S)").unwrap().dst);
+        assert!(TST::parse_lenient("(1903201814S)").unwrap().dst);
+        assert!(TST::parse_lenient("(19032018140X)").is_err());
+    }
+
+    #[test]
+    fn tst_to_unix() {
+        let winter = TST::parse("(190320181403W)").unwrap();
+        assert_eq!(winter.to_unix(), Some(1553102043));
+
+        let summer = TST::parse("(220901152201S)").unwrap();
+        assert_eq!(summer.to_unix(), Some(1662038521));
+
+        let leap = TST::parse("(200229000000W)").unwrap();
+        assert_eq!(leap.to_unix(), Some(1582930800));
+
+        assert_eq!(TST::parse("(000000000000W)").unwrap().to_unix(), None);
+        assert_eq!(TST::parse("(190229000000W)").unwrap().to_unix(), None);
+    }
+
+    #[test]
+    fn tst_difference_seconds() {
+        let a = TST::parse("(190320181403W)").unwrap();
+        let b = TST::parse("(190320181003W)").unwrap();
+        assert_eq!(a.difference_seconds(&b), Some(240));
+        assert_eq!(b.difference_seconds(&a), Some(-240));
+
+        // Across the switch to summer time, 02:00 winter time equals 03:00 summer time.
+        let before = TST::parse("(190331015959W)").unwrap();
+        let after = TST::parse("(190331030000S)").unwrap();
+        assert_eq!(after.difference_seconds(&before), Some(1));
+
+        let invalid = TST::parse("(000000000000W)").unwrap();
+        assert_eq!(a.difference_seconds(&invalid), None);
+    }
+
+    #[test]
+    fn tst_cmp_instant() {
+        use core::cmp::Ordering::*;
+
+        // 02:30 winter time is 01:30 UTC, whereas 01:30 summer time is 23:30 UTC the day before.
+        let winter = TST::parse("(190331023000W)").unwrap();
+        let summer = TST::parse("(190331013000S)").unwrap();
+        assert_eq!(summer.cmp_instant(&winter), Less);
+        assert_eq!(
+            TST::parse("(190331033000W)")
+                .unwrap()
+                .cmp_instant(&TST::parse("(190331033000S)").unwrap()),
+            Greater
+        );
+
+        // The same instant, expressed in winter and summer time, differs only by its fields.
+        let winter = TST::parse("(191027020000W)").unwrap();
+        let summer = TST::parse("(191027030000S)").unwrap();
+        assert_eq!(winter.cmp_instant(&summer), Equal);
+        assert_ne!(winter, summer);
+
+        let mut timestamps = [
+            TST::parse("(190320181403W)").unwrap(),
+            TST::parse("(190320181003W)").unwrap(),
+        ];
+        timestamps.sort_by(TST::cmp_instant);
+        assert_eq!(timestamps[0], TST::parse("(190320181003W)").unwrap());
+    }
+
+    #[test]
+    fn tst_to_utc() {
+        let utc = |body| {
+            let tst = TST::parse(body).unwrap().to_utc();
+            assert!(!tst.dst);
+            (
+                tst.year, tst.month, tst.day, tst.hour, tst.minute, tst.second,
+            )
+        };
+
+        assert_eq!(utc("(190320181403W)"), (19, 3, 20, 17, 14, 3));
+        assert_eq!(utc("(190321003000W)"), (19, 3, 20, 23, 30, 0));
+        assert_eq!(utc("(190401013000S)"), (19, 3, 31, 23, 30, 0));
+        assert_eq!(utc("(200301003000W)"), (20, 2, 29, 23, 30, 0));
+        assert_eq!(utc("(200101000000W)"), (19, 12, 31, 23, 0, 0));
+
+        assert_eq!(
+            TST::parse("(190320181403W)").unwrap().utc_offset_seconds(),
+            3600
+        );
+        assert_eq!(
+            TST::parse("(220901152201S)").unwrap().utc_offset_seconds(),
+            7200
+        );
+    }
+
+    #[test]
+    fn tst_display() {
+        let winter = TST::parse("(190320181403W)").unwrap();
+        assert_eq!(format!("{}", winter), "2019-03-20T18:14:03+01:00");
+
+        let summer = TST::parse("(220901152201S)").unwrap();
+        assert_eq!(format!("{}", summer), "2022-09-01T15:22:01+02:00");
+    }
+
+    #[test]
+    fn tst_parse_rfc3339() {
+        let tst = TST::parse_rfc3339("2019-03-20T18:14:03+01:00").unwrap();
+        assert_eq!(tst, TST::parse("(190320181403W)").unwrap());
+        assert!(!tst.dst);
+
+        let tst = TST::parse_rfc3339("2022-09-01T15:22:01+02:00").unwrap();
+        assert!(tst.dst);
+
+        assert!(TST::parse_rfc3339("2019-03-20T18:14:03Z").is_err());
+        assert!(TST::parse_rfc3339("2019-03-20T18:14:03+00:00").is_err());
+        assert!(TST::parse_rfc3339("2019-03-20 18:14:03+01:00").is_err());
+        assert!(TST::parse_rfc3339("1999-03-20T18:14:03+01:00").is_err());
+    }
+
+    #[cfg(feature = "serde-rfc3339")]
+    #[test]
+    fn tst_serde_rfc3339() {
+        let tst = TST::parse("(190320181403W)").unwrap();
+        let json = serde_json::to_string(&tst).unwrap();
+        assert_eq!(json, "\"2019-03-20T18:14:03+01:00\"");
+
+        let back: TST = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, tst);
+        assert!(!back.dst);
+
+        assert!(serde_json::from_str::<TST>("\"2019-03-20T18:14:03Z\"").is_err());
+    }
+
+    #[test]
+    fn ufixeddouble_parse_max() {
+        let v = |body| f64::from(&UFixedDouble::parse_max(body, 5).unwrap());
+
+        assert_eq!(v("(236.1*V)"), 236.1);
+        assert_eq!(v("(236.12*V)"), 236.12);
+        assert_eq!(v("(00.193*kW)"), 0.193);
+        assert!(UFixedDouble::parse_max("(000236.12*V)", 5).is_err());
+        assert!(UFixedDouble::parse_max("(236*V)", 5).is_err());
+    }
+
+    #[test]
+    fn ufixeddouble_comma_separator() {
+        let dot = UFixedDouble::parse("(000576.239*kWh)", 9, 3).unwrap();
+        let comma = UFixedDouble::parse("(000576,239*kWh)", 9, 3).unwrap();
+        assert_eq!(f64::from(&comma), f64::from(&dot));
+
+        let comma = UFixedDouble::parse_max("(236,12*V)", 5).unwrap();
+        assert_eq!(f64::from(&comma), 236.12);
+
+        assert!(UFixedDouble::parse("(000576;239*kWh)", 9, 3).is_err());
+    }
+
+    #[test]
+    fn ufixeddouble_parse_with_unit() {
+        let unit = |body, length, point| {
+            UFixedDouble::parse_with_unit(body, length, point)
+                .unwrap()
+                .1
+        };
+
+        assert_eq!(unit("(00.193*kW)", 5, 3), Unit::Kilowatt);
+        assert_eq!(unit("(000576.239*kWh)", 9, 3), Unit::KilowattHour);
+        assert_eq!(unit("(00.000*kvar)", 5, 3), Unit::Kilovar);
+        assert_eq!(unit("(000000.000*kvarh)", 9, 3), Unit::KilovarHour);
+        assert_eq!(unit("(236.1*V)", 4, 1), Unit::Volt);
+        assert_eq!(unit("(001.5*A)", 4, 1), Unit::Ampere);
+        assert_eq!(unit("(00304.089*m3)", 8, 3), Unit::CubicMeter);
+        assert_eq!(unit("(00012.345*GJ)", 8, 3), Unit::Gigajoule);
+        assert_eq!(unit("(0000003.231*s)", 10, 3), Unit::Second);
+
+        let (value, _) = UFixedDouble::parse_with_unit("(000576.239*kWh)", 9, 3).unwrap();
+        assert_eq!(f64::from(&value), 576.239);
+
+        assert!(matches!(
+            UFixedDouble::parse_with_unit("(00.193*MW)", 5, 3),
+            Err(Error::InvalidFormat)
+        ));
+        assert!(UFixedDouble::parse_with_unit("(00.193)", 5, 3).is_err());
+        assert!(UFixedDouble::parse_with_unit("(00.193*kW", 5, 3).is_err());
+
+        assert_eq!(format!("{}", Unit::KilovarHour), "kvarh");
+    }
+
+    #[test]
+    fn ufixeddouble_scaled() {
+        let reading = UFixedDouble::parse("(00576.239*kWh)", 8, 3).unwrap();
+        assert_eq!(reading.as_scaled(), (576239, 3));
+        assert_eq!(reading.to_millis(), Some(576239));
+
+        let voltage = UFixedDouble::parse("(236.1*V)", 4, 1).unwrap();
+        assert_eq!(voltage.as_scaled(), (2361, 1));
+        assert_eq!(voltage.to_millis(), Some(236100));
+
+        let precise = UFixedDouble::parse("(1.23456*kW)", 6, 5).unwrap();
+        assert_eq!(precise.to_millis(), Some(1234));
+
+        // Values beyond the range of a u64 in thousandths.
+        let limit = UFixedDouble::parse_max("(18446744073709551.6)", 18).unwrap();
+        assert_eq!(limit.to_millis(), Some(18446744073709551600));
+        let large = UFixedDouble::parse_max("(18446744073709551.7)", 18).unwrap();
+        assert_eq!(large.to_millis(), None);
+        let whole = UFixedDouble::from(UFixedInteger(u64::MAX));
+        assert_eq!(whole.to_millis(), None);
+    }
+
+    #[test]
+    fn ufixeddouble_overflow() {
+        assert!(matches!(
+            UFixedDouble::parse("(99999999999999999.999*kWh)", 20, 3),
+            Err(Error::InvalidFormat)
+        ));
+        assert!(matches!(
+            UFixedDouble::parse_max("(99999999999999999999999999.9*kWh)", 30),
+            Err(Error::InvalidFormat)
+        ));
+        assert!(matches!(
+            UFixedDouble::parse_max("(0.00000000000000000000001*kWh)", 30),
+            Err(Error::InvalidFormat)
+        ));
+        assert!(matches!(
+            UFixedDouble::parse("(1.5*kW)", 2, 5),
+            Err(Error::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn ufixeddouble_ord() {
+        let v = |body| UFixedDouble::parse_max(body, 9).unwrap();
+
+        assert_eq!(v("(1.5*kW)"), v("(1.50*kW)"));
+        assert_eq!(v("(001.500*kW)"), v("(1.5*kW)"));
+        assert!(v("(1.5*kW)") < v("(1.51*kW)"));
+        assert!(v("(1.49*kW)") < v("(1.5*kW)"));
+        assert!(v("(10.0*kW)") > v("(9.999*kW)"));
+        assert_eq!(v("(1.5*kW)").max(v("(1.25*kW)")), v("(1.5*kW)"));
+    }
+
+    #[test]
+    fn ufixeddouble_display() {
+        let v = |body| format!("{}", UFixedDouble::parse_max(body, 9).unwrap());
+
+        assert_eq!(v("(000576.239*kWh)"), "576.239");
+        assert_eq!(v("(00.093*kW)"), "0.093");
+        assert_eq!(v("(000000.000*kWh)"), "0.000");
+        assert_eq!(v("(236.1*V)"), "236.1");
+    }
+
+    #[test]
+    fn sfixeddouble() {
+        let v = |body| f64::from(&SFixedDouble::parse(body, 4, 3).unwrap());
+
+        assert_eq!(v("(-0.123*kW)"), -0.123);
+        assert_eq!(v("(0.456*kW)"), 0.456);
+        assert_eq!(v("(-0,123*kW)"), -0.123);
+        assert!(SFixedDouble::parse("(--0.123*kW)", 4, 3).is_err());
+        assert!(SFixedDouble::parse("(+0.123*kW)", 4, 3).is_err());
+
+        let v = |body| f64::from(&SFixedDouble::parse_max(body, 5).unwrap());
+        assert_eq!(v("(-01.5*kW)"), -1.5);
+        assert_eq!(v("(12.34*kW)"), 12.34);
+    }
+
+    #[test]
+    fn octetstring_decode_into() {
+        let id = OctetString::parse_max("(4530303433303037303532383730333138)", 96).unwrap();
+
+        let mut buf = [0u8; 48];
+        assert_eq!(id.decode_into(&mut buf).unwrap(), "E0043007052870318");
+        assert!(id.decode_into(&mut buf[..16]).is_err());
+
+        let odd = OctetString::parse_max("(453)", 96).unwrap();
+        assert!(odd.decode_into(&mut buf).is_err());
+
+        let invalid = OctetString::parse_max("(45FF)", 96).unwrap();
+        assert!(invalid.decode_into(&mut buf).is_err());
+    }
+
+    #[test]
+    fn octetstring_decode_ascii_into() {
+        let file = std::fs::read("test/kaifa.txt").unwrap();
+        let telegram = crate::BorrowedReadout(&file).to_telegram().unwrap();
+        let id = telegram
+            .objects()
+            .find_map(|o| match o {
+                Ok(crate::OBIS::EquipmentIdentifier(id)) => Some(id),
+                _ => None,
+            })
+            .unwrap();
+
+        let mut buf = [0u8; 96];
+        assert_eq!(id.decode_ascii_into(&mut buf).unwrap(), "E0026000024153615");
+
+        let accented = OctetString::parse_max("(C3A9)", 96).unwrap();
+        assert_eq!(accented.decode_into(&mut buf).unwrap(), "\u{e9}");
+        assert!(accented.decode_ascii_into(&mut buf).is_err());
+    }
+
+    #[test]
+    fn dsmr_version() {
+        let v = |body| DsmrVersion::parse(&OctetString::parse_max(body, 6).unwrap());
+
+        assert_eq!(v("(50)").unwrap(), DsmrVersion { major: 5, minor: 0 });
+        assert_eq!(v("(42)").unwrap(), DsmrVersion { major: 4, minor: 2 });
+        assert_eq!(v("(5021)").unwrap(), DsmrVersion { major: 5, minor: 0 });
+        assert!(v("(5A)").is_err());
+        assert!(v("()").is_err());
+
+        // The parser keeps all octets of the version, rather than only the first.
+        let crate::OBIS::Version(version) = crate::parse_object("1-3:0.2.8(5021)").unwrap() else {
+            panic!();
+        };
+        assert_eq!(std::format!("{}", version), "5021");
+        assert_eq!(
+            DsmrVersion::parse(&version).unwrap(),
+            DsmrVersion { major: 5, minor: 0 }
+        );
+        assert!(crate::parse_object("1-3:0.2.8(50210000)").is_err());
+    }
+}